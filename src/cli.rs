@@ -0,0 +1,158 @@
+use crate::database::{validate_databases, IssueLevel};
+use crate::export::{export_runs, runs_since, ExportFormat};
+use crate::get_track_record;
+use crate::import::{import_runs, read_runs, ColumnMapping, ImportFormat};
+use crate::profile::{current_profile, list_profiles, migrate_single_user};
+use chrono::NaiveDate;
+use std::fs::File;
+use std::io;
+
+const USAGE: &str = "Usage:
+  trace-game                     Start the game
+  trace-game stats export [--format json|csv|md] [--since YYYY-MM-DD] [--output FILE]
+                                 Export the run history, to stdout when there's no output file
+  trace-game stats import FILE [--format monkeytype|csv] [--map FIELD=COLUMN,...] [--source NAME]
+                                 Add the runs of another typing tool to the history
+  trace-game validate-db          Check the databases for paragraphs that can't be typed, missing
+                                 fields, repeated paragraphs and files that can't be read
+
+The stats commands use the last profile played unless --profile NAME is given.";
+
+/// Runs the subcommand in `args`, the arguments after the program name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["stats", "export", options @ ..] => stats_export(options),
+        ["stats", "import", file, options @ ..] => stats_import(file, options),
+        ["validate-db"] => validate_db(),
+        ["help"] | ["--help"] | ["-h"] => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("Unknown command \"{}\"\n{}", args.join(" "), USAGE)),
+    }
+}
+
+/// The profile named by `--profile`, or the last one used.
+fn profile_option(profile: Option<&str>) -> Result<String, String> {
+    migrate_single_user()
+        .map_err(|err| format!("Couldn't move the old user to a profile: {}", err))?;
+    let profile = match profile {
+        Some(profile) => profile.to_string(),
+        None => current_profile().ok_or("There's no profile yet, play once or use --profile")?,
+    };
+    if !list_profiles().contains(&profile) {
+        return Err(format!("There's no profile named \"{}\"", profile));
+    }
+    Ok(profile)
+}
+
+/// Splits `--name value` pairs.
+fn parse_options<'a>(
+    options: &[&'a str], known: &[&str],
+) -> Result<Vec<(&'a str, &'a str)>, String> {
+    options
+        .chunks(2)
+        .map(|pair| match pair {
+            [name, value] if known.contains(name) => Ok((*name, *value)),
+            [name] if known.contains(name) => Err(format!("Missing value for {}", name)),
+            [name, ..] => Err(format!("Unknown option \"{}\"\n{}", name, USAGE)),
+            [] => unreachable!(),
+        })
+        .collect()
+}
+
+fn stats_export(options: &[&str]) -> Result<(), String> {
+    let mut format = ExportFormat::Csv;
+    let mut since = None;
+    let mut output = None;
+    let mut profile = None;
+    for (name, value) in parse_options(options, &["--format", "--since", "--output", "--profile"])?
+    {
+        match name {
+            "--format" => {
+                format = ExportFormat::from_name(value)
+                    .ok_or_else(|| format!("Unknown format \"{}\", use json, csv or md", value))?
+            }
+            "--since" => {
+                since = Some(
+                    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                        .map_err(|_| format!("Invalid date \"{}\", use YYYY-MM-DD", value))?,
+                )
+            }
+            "--output" => output = Some(value),
+            _ => profile = Some(value),
+        }
+    }
+
+    let runs = get_track_record(&profile_option(profile)?);
+    let indexes = runs_since(&runs, since);
+    match output {
+        Some(path) => {
+            let file = File::create(path)
+                .map_err(|err| format!("Couldn't create \"{}\": {}", path, err))?;
+            export_runs(&runs, &indexes, format, file)?;
+            eprintln!("Exported {} runs to {}", indexes.len(), path);
+            Ok(())
+        }
+        None => export_runs(&runs, &indexes, format, io::stdout().lock()),
+    }
+}
+
+fn stats_import(file: &str, options: &[&str]) -> Result<(), String> {
+    let mut format = ImportFormat::Csv;
+    let mut mapping = ColumnMapping::default();
+    let mut source = None;
+    let mut profile = None;
+    for (name, value) in parse_options(options, &["--format", "--map", "--source", "--profile"])? {
+        match name {
+            "--format" => {
+                format = ImportFormat::from_name(value)
+                    .ok_or_else(|| format!("Unknown format \"{}\", use monkeytype or csv", value))?
+            }
+            "--map" => mapping = ColumnMapping::parse(value)?,
+            "--source" => source = Some(value),
+            _ => profile = Some(value),
+        }
+    }
+
+    let profile = profile_option(profile)?;
+    let reader = File::open(file).map_err(|err| format!("Couldn't open \"{}\": {}", file, err))?;
+    let source = source.unwrap_or_else(|| format.default_source());
+    let runs = read_runs(reader, format, &mapping, source)?;
+    let read = runs.len();
+    let added = import_runs(&profile, runs)?;
+    eprintln!(
+        "Imported {} runs from {}, {} were already in the history",
+        added,
+        file,
+        read - added
+    );
+    Ok(())
+}
+
+fn validate_db() -> Result<(), String> {
+    let issues = validate_databases();
+    for issue in &issues {
+        let level = match issue.level {
+            IssueLevel::Error => "error",
+            IssueLevel::Warning => "warning",
+        };
+        println!("{}: {}", level, issue);
+    }
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.level == IssueLevel::Error)
+        .count();
+    let summary = format!("{} errors, {} warnings", errors, issues.len() - errors);
+    if errors > 0 {
+        Err(summary)
+    } else {
+        eprintln!("{}", summary);
+        Ok(())
+    }
+}
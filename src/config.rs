@@ -0,0 +1,567 @@
+use crate::{get_app_path, profile::profile_path, theme::Theme};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{collections::HashMap, convert::TryFrom, path::PathBuf};
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    pub key_bindings: KeyBindings,
+    pub theme: Theme,
+    pub typing: TypingOptions,
+}
+
+impl Config {
+    /// Reads `config.toml` from the app directory, a missing file means the default config.
+    pub fn load() -> Result<Config, String> {
+        Config::load_from(get_app_path("config.toml"))
+    }
+
+    /// Reads the `config.toml` of the profile, profiles without one use the config of the app
+    /// directory.
+    pub fn load_profile(profile: &str) -> Result<Config, String> {
+        let path = profile_path(profile, "config.toml");
+        if path.exists() {
+            Config::load_from(path)
+        } else {
+            Config::load()
+        }
+    }
+
+    fn load_from(path: PathBuf) -> Result<Config, String> {
+        match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|err| format!("{} is not a valid config file:\n{}", path.display(), err)),
+            Err(_) => Ok(Config::default()),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CaretStyle {
+    #[default]
+    Block,
+    Underline,
+    Bar,
+    None,
+}
+
+/// How the paragraph is drawn while typing.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct TypingOptions {
+    pub caret: CaretStyle,
+    /// Show the wrong char that was typed instead of the expected one.
+    pub show_typed_errors: bool,
+    /// Chars typed at the end of a word are inserted before the space instead of replacing it.
+    pub show_extra_chars: bool,
+    /// Lines of the paragraph shown at once, the current line is kept in the middle.
+    pub visible_lines: usize,
+}
+
+impl Default for TypingOptions {
+    fn default() -> Self {
+        TypingOptions {
+            caret: CaretStyle::default(),
+            show_typed_errors: false,
+            show_extra_chars: false,
+            visible_lines: 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Practice,
+    Statistics,
+    Multiplayer,
+    Exit,
+    Menu,
+    Reset,
+    JoinRoom,
+    NewRoom,
+    Back,
+    ToggleChart,
+    PreviousPage,
+    NextPage,
+    PreviousRun,
+    NextRun,
+    CycleRange,
+    Dashboard,
+    Export,
+    CycleSource,
+    Profiles,
+    NewProfile,
+    RenameProfile,
+    DeleteProfile,
+    Library,
+    Search,
+    ToggleFavourite,
+    ToggleHidden,
+    CycleFilter,
+    Book,
+    Continue,
+    Language,
+    Options,
+    CycleDifficulty,
+    Daily,
+    ExportJson,
+    ExportCsv,
+    ExportMarkdown,
+}
+
+impl Action {
+    /// Name used for the action in the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Practice => "practice",
+            Action::Statistics => "statistics",
+            Action::Multiplayer => "multiplayer",
+            Action::Exit => "exit",
+            Action::Menu => "menu",
+            Action::Reset => "reset",
+            Action::JoinRoom => "join_room",
+            Action::NewRoom => "new_room",
+            Action::Back => "back",
+            Action::ToggleChart => "toggle_chart",
+            Action::PreviousPage => "previous_page",
+            Action::NextPage => "next_page",
+            Action::PreviousRun => "previous_run",
+            Action::NextRun => "next_run",
+            Action::CycleRange => "cycle_range",
+            Action::Dashboard => "dashboard",
+            Action::Export => "export",
+            Action::CycleSource => "cycle_source",
+            Action::Profiles => "profiles",
+            Action::NewProfile => "new_profile",
+            Action::RenameProfile => "rename_profile",
+            Action::DeleteProfile => "delete_profile",
+            Action::Library => "library",
+            Action::Search => "search",
+            Action::ToggleFavourite => "toggle_favourite",
+            Action::ToggleHidden => "toggle_hidden",
+            Action::CycleFilter => "cycle_filter",
+            Action::Book => "book",
+            Action::Continue => "continue",
+            Action::Language => "language",
+            Action::Options => "options",
+            Action::CycleDifficulty => "cycle_difficulty",
+            Action::Daily => "daily",
+            Action::ExportJson => "export_json",
+            Action::ExportCsv => "export_csv",
+            Action::ExportMarkdown => "export_markdown",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::all()
+            .into_iter()
+            .find(|action| action.name() == name)
+    }
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Practice => &["p", "P"],
+            Action::Statistics => &["s", "S"],
+            Action::Multiplayer => &["m", "M"],
+            Action::Exit => &["e", "E"],
+            Action::Menu => &["m", "M"],
+            Action::Reset => &["r", "R"],
+            Action::JoinRoom => &["j", "J"],
+            Action::NewRoom => &["n", "N"],
+            Action::Back => &["Esc"],
+            Action::ToggleChart => &["Tab"],
+            Action::PreviousPage => &["Left", "PageUp"],
+            Action::NextPage => &["Right", "PageDown"],
+            Action::PreviousRun => &["h", ","],
+            Action::NextRun => &["l", "."],
+            Action::CycleRange => &["f", "F"],
+            Action::Dashboard => &["d", "D"],
+            Action::Export => &["x", "X"],
+            Action::CycleSource => &["o", "O"],
+            Action::Profiles => &["u", "U"],
+            Action::NewProfile => &["n", "N"],
+            Action::RenameProfile => &["r", "R"],
+            Action::DeleteProfile => &["d", "D"],
+            Action::Library => &["l", "L"],
+            Action::Search => &["/"],
+            Action::ToggleFavourite => &["f", "F"],
+            Action::ToggleHidden => &["h", "H"],
+            Action::CycleFilter => &["Tab"],
+            Action::Book => &["b", "B"],
+            Action::Continue => &["c", "C"],
+            Action::Language => &["g", "G"],
+            Action::Options => &["o", "O"],
+            Action::CycleDifficulty => &["i", "I"],
+            Action::Daily => &["d", "D"],
+            Action::ExportJson => &["j", "J"],
+            Action::ExportCsv => &["c", "C"],
+            Action::ExportMarkdown => &["m", "M"],
+        }
+    }
+    fn all() -> [Action; 36] {
+        [
+            Action::Practice,
+            Action::Statistics,
+            Action::Multiplayer,
+            Action::Exit,
+            Action::Menu,
+            Action::Reset,
+            Action::JoinRoom,
+            Action::NewRoom,
+            Action::Back,
+            Action::ToggleChart,
+            Action::PreviousPage,
+            Action::NextPage,
+            Action::PreviousRun,
+            Action::NextRun,
+            Action::CycleRange,
+            Action::Dashboard,
+            Action::Export,
+            Action::CycleSource,
+            Action::Profiles,
+            Action::NewProfile,
+            Action::RenameProfile,
+            Action::DeleteProfile,
+            Action::Library,
+            Action::Search,
+            Action::ToggleFavourite,
+            Action::ToggleHidden,
+            Action::CycleFilter,
+            Action::Book,
+            Action::Continue,
+            Action::Language,
+            Action::Options,
+            Action::CycleDifficulty,
+            Action::Daily,
+            Action::ExportJson,
+            Action::ExportCsv,
+            Action::ExportMarkdown,
+        ]
+    }
+}
+
+/// Keys assigned to every [`Action`]. Actions missing from the config file keep their defaults.
+#[derive(Deserialize, Clone)]
+#[serde(try_from = "HashMap<String, Vec<String>>")]
+pub struct KeyBindings {
+    bindings: HashMap<Action, Vec<KeyEvent>>,
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: Action) -> &[KeyEvent] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Splits a button label into the activator and the rest of the text, using the first key
+    /// bound to the action. When the key isn't the first letter of the label it's shown in front.
+    pub fn button_label(&self, action: Action, label: &str) -> (String, String) {
+        let key = match self.keys(action).first() {
+            Some(key) => *key,
+            None => return (String::new(), label.to_string()),
+        };
+        let mut label_chars = label.chars();
+        match (key.code, label_chars.next()) {
+            (KeyCode::Char(c), Some(first))
+                if key.modifiers.is_empty() && c.to_lowercase().eq(first.to_lowercase()) =>
+            {
+                (first.to_uppercase().to_string(), label_chars.collect())
+            }
+            _ => (format!("[{}]", key_to_string(key)), format!(" {}", label)),
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let bindings = Action::all()
+            .iter()
+            .map(|action| {
+                let keys = action
+                    .default_keys()
+                    .iter()
+                    .map(|k| parse_key(k).expect("Default key bindings should be valid"))
+                    .collect();
+                (*action, keys)
+            })
+            .collect();
+        KeyBindings { bindings }
+    }
+}
+
+/// Actions that are active at the same time, in the same window, so they can't share keys.
+const WINDOW_ACTIONS: [&[Action]; 7] = [
+    &[
+        Action::Practice,
+        Action::Statistics,
+        Action::Multiplayer,
+        Action::Exit,
+        Action::Back,
+        Action::Profiles,
+        Action::Library,
+        Action::Language,
+        Action::Options,
+        Action::Daily,
+    ],
+    &[
+        Action::Back,
+        Action::Continue,
+        Action::Exit,
+        Action::Menu,
+        Action::Reset,
+        Action::Statistics,
+    ],
+    &[Action::Back, Action::JoinRoom, Action::NewRoom],
+    &[
+        Action::Back,
+        Action::ToggleChart,
+        Action::PreviousPage,
+        Action::NextPage,
+        Action::PreviousRun,
+        Action::NextRun,
+        Action::CycleRange,
+        Action::Dashboard,
+        Action::Export,
+        Action::CycleSource,
+        Action::Language,
+        Action::CycleDifficulty,
+    ],
+    &[
+        Action::Back,
+        Action::ExportJson,
+        Action::ExportCsv,
+        Action::ExportMarkdown,
+    ],
+    &[
+        Action::Back,
+        Action::NewProfile,
+        Action::RenameProfile,
+        Action::DeleteProfile,
+    ],
+    &[
+        Action::Back,
+        Action::PreviousPage,
+        Action::NextPage,
+        Action::Search,
+        Action::ToggleFavourite,
+        Action::ToggleHidden,
+        Action::CycleFilter,
+        Action::Book,
+    ],
+];
+
+impl KeyBindings {
+    /// Fails when a key closes the app, since it never reaches the windows, or when two actions
+    /// of the same window share a key.
+    fn check(&self) -> Result<(), String> {
+        for (action, keys) in &self.bindings {
+            if let Some(key) = keys.iter().find(|key| crate::is_quit_event(**key)) {
+                return Err(format!(
+                    "\"{}\" can't be bound to {}, it closes the app",
+                    key_to_string(*key),
+                    action.name()
+                ));
+            }
+        }
+        for actions in WINDOW_ACTIONS {
+            for (i, first) in actions.iter().enumerate() {
+                for second in &actions[i + 1..] {
+                    if let Some(key) = self
+                        .keys(*first)
+                        .iter()
+                        .find(|key| self.keys(*second).contains(key))
+                    {
+                        return Err(format!(
+                            "\"{}\" is bound to both {} and {}",
+                            key_to_string(*key),
+                            first.name(),
+                            second.name()
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<HashMap<String, Vec<String>>> for KeyBindings {
+    type Error = String;
+
+    fn try_from(raw: HashMap<String, Vec<String>>) -> Result<Self, Self::Error> {
+        let mut key_bindings = KeyBindings::default();
+        for (name, keys) in raw {
+            let action =
+                Action::from_name(&name).ok_or_else(|| format!("Unknown action \"{}\"", name))?;
+            let parsed = keys
+                .iter()
+                .map(|k| parse_key(k).ok_or_else(|| format!("Unknown key \"{}\"", k)))
+                .collect::<Result<Vec<KeyEvent>, String>>()?;
+            key_bindings.bindings.insert(action, parsed);
+        }
+        key_bindings.check()?;
+        Ok(key_bindings)
+    }
+}
+
+/// Parses keys like `p`, `Esc`, `Ctrl+q` or `Alt+Enter`.
+pub fn parse_key(key: &str) -> Option<KeyEvent> {
+    let parts: Vec<&str> = key.split('+').collect();
+    // A lone "+" (or "Ctrl++") means the plus key itself.
+    let (modifier_parts, key_part) = match parts.as_slice() {
+        [.., "", ""] => (&parts[..parts.len() - 2], "+"),
+        [rest @ .., last] => (rest, *last),
+        [] => return None,
+    };
+
+    let mut modifiers = KeyModifiers::empty();
+    for modifier in modifier_parts {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+    }
+
+    let mut chars = key_part.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key_part.to_lowercase().as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            f => match f.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                _ => return None,
+            },
+        },
+    };
+    Some(crate::normalize_key_event(KeyEvent::new(code, modifiers)))
+}
+
+pub fn key_to_string(key: KeyEvent) -> String {
+    let mut text = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        text.push_str("Ctrl+");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        text.push_str("Alt+");
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) {
+        text.push_str("Shift+");
+    }
+    let code = match key.code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::BackTab => "BackTab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::PageUp => "PageUp".to_string(),
+        KeyCode::PageDown => "PageDown".to_string(),
+        KeyCode::Delete => "Delete".to_string(),
+        KeyCode::Insert => "Insert".to_string(),
+        KeyCode::Null => "Null".to_string(),
+    };
+    text.push_str(&code);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(pairs: &[(&str, &[&str])]) -> Result<KeyBindings, String> {
+        let raw = pairs
+            .iter()
+            .map(|(name, keys)| {
+                (
+                    name.to_string(),
+                    keys.iter().map(|k| k.to_string()).collect(),
+                )
+            })
+            .collect::<HashMap<String, Vec<String>>>();
+        KeyBindings::try_from(raw)
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        for key in [
+            "p",
+            "P",
+            "Esc",
+            "Ctrl+x",
+            "Alt+Enter",
+            "Space",
+            "F5",
+            "+",
+            "Ctrl++",
+            "/",
+        ] {
+            let parsed = parse_key(key).unwrap();
+            assert_eq!(key_to_string(parsed), key);
+            assert_eq!(parse_key(&key_to_string(parsed)), Some(parsed));
+        }
+        assert_eq!(parse_key("escape"), parse_key("Esc"));
+        assert_eq!(parse_key("F13"), None);
+        assert_eq!(parse_key("Hyper+a"), None);
+    }
+
+    #[test]
+    fn the_default_bindings_are_valid() {
+        assert!(KeyBindings::default().check().is_ok());
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_errors() {
+        assert_eq!(
+            bindings(&[("jump", &["j"])]).err().unwrap(),
+            "Unknown action \"jump\""
+        );
+        assert_eq!(
+            bindings(&[("practice", &["Hyper+p"])]).err().unwrap(),
+            "Unknown key \"Hyper+p\""
+        );
+    }
+
+    #[test]
+    fn quit_keys_cant_be_bound() {
+        assert_eq!(
+            bindings(&[("practice", &["Ctrl+q"])]).err().unwrap(),
+            "\"Ctrl+q\" can't be bound to practice, it closes the app"
+        );
+        assert!(bindings(&[("reset", &["Ctrl+C"])]).is_err());
+    }
+
+    #[test]
+    fn actions_of_a_window_cant_share_keys() {
+        assert_eq!(
+            bindings(&[("practice", &["s"])]).err().unwrap(),
+            "\"s\" is bound to both practice and statistics"
+        );
+        // The reset and the delete profile actions are in different windows.
+        let key_bindings = bindings(&[("reset", &["x"]), ("delete_profile", &["x"])]).unwrap();
+        assert_eq!(key_bindings.keys(Action::Reset), &[parse_key("x").unwrap()]);
+    }
+}
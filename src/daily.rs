@@ -0,0 +1,298 @@
+use crate::library::{load_profile_toml, paragraph_key, save_profile_toml, SeededRandom};
+use crate::profile::list_profiles;
+use crate::{AppParagraph, TraceRun};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const DAILY_FILE: &str = "daily.toml";
+
+/// Key of the days in the `daily.toml` files.
+pub fn date_key(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Seed of the challenge of a date. It mixes the date with the paragraphs of the databases, so
+/// everyone with the same databases gets the same challenge, and changing them changes it.
+pub fn daily_seed(date: NaiveDate, paragraphs: &[AppParagraph]) -> u64 {
+    let mut keys: Vec<String> = paragraphs.iter().map(paragraph_key).collect();
+    keys.sort();
+    // FNV-1a, like `paragraph_key`.
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let bytes = date_key(date)
+        .into_bytes()
+        .into_iter()
+        .chain(keys.iter().flat_map(|key| key.bytes()));
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Paragraph of the challenge of a date, every paragraph of the databases can be picked.
+pub fn daily_paragraph(date: NaiveDate, paragraphs: &[AppParagraph]) -> Option<AppParagraph> {
+    if paragraphs.is_empty() {
+        return None;
+    }
+    // Sorted so the order of the files doesn't matter.
+    let mut paragraphs = paragraphs.to_vec();
+    paragraphs.sort_by_cached_key(paragraph_key);
+    let index = SeededRandom::new(daily_seed(date, &paragraphs)).below(paragraphs.len());
+    Some(paragraphs.swap_remove(index))
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct DailyScore {
+    pub wpm: f64,
+    pub accuracy: f64,
+    pub points: f64,
+    pub seconds: f64,
+}
+
+impl DailyScore {
+    pub fn from_run(run: &TraceRun) -> DailyScore {
+        DailyScore {
+            wpm: run.wpm,
+            accuracy: run.accuracy,
+            points: run.total_points,
+            seconds: run.seconds,
+        }
+    }
+}
+
+/// Attempt of a profile at the challenge of a day.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct DailyAttempt {
+    /// Key of the paragraph of the challenge.
+    pub paragraph: String,
+    /// Missing when the attempt was left unfinished, it still counts as the attempt of the day.
+    pub score: Option<DailyScore>,
+}
+
+/// Daily challenges of a profile, saved in its `daily.toml`.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct DailyRecord {
+    pub days: BTreeMap<String, DailyAttempt>,
+}
+
+impl DailyRecord {
+    pub fn load(profile: &str) -> DailyRecord {
+        load_profile_toml(profile, DAILY_FILE)
+    }
+
+    pub fn save(&self, profile: &str) -> Result<(), String> {
+        save_profile_toml(self, profile, DAILY_FILE)
+    }
+
+    pub fn attempt(&self, date: NaiveDate) -> Option<&DailyAttempt> {
+        self.days.get(&date_key(date))
+    }
+
+    /// Days in a row with a finished challenge, up to `today`. Today doesn't break the streak
+    /// until it's over.
+    pub fn streak(&self, today: NaiveDate) -> usize {
+        let finished = |date: NaiveDate| {
+            self.attempt(date)
+                .is_some_and(|attempt| attempt.score.is_some())
+        };
+        let mut date = if finished(today) {
+            today
+        } else {
+            today - Duration::days(1)
+        };
+        let mut streak = 0;
+        while finished(date) {
+            streak += 1;
+            date -= Duration::days(1);
+        }
+        streak
+    }
+}
+
+/// Challenge of a day, with the results of the profiles of this computer.
+#[derive(Clone)]
+pub struct Daily {
+    pub date: NaiveDate,
+    pub paragraph: AppParagraph,
+    /// The run being typed is the scored attempt of the day.
+    pub scored: bool,
+    /// Whether the profile already used its attempt of the day.
+    pub attempted: bool,
+    pub streak: usize,
+    /// Names and scores of the profiles that finished the challenge, best first.
+    pub leaderboard: Vec<(String, DailyScore)>,
+}
+
+impl Daily {
+    pub fn load(profile: &str, date: NaiveDate, paragraph: AppParagraph) -> Daily {
+        let key = paragraph_key(&paragraph);
+        let mut leaderboard: Vec<(String, DailyScore)> = list_profiles()
+            .into_iter()
+            .filter_map(|name| {
+                let attempt = DailyRecord::load(&name).attempt(date)?.clone();
+                // Profiles with other databases had another challenge.
+                if attempt.paragraph != key {
+                    return None;
+                }
+                Some((name, attempt.score?))
+            })
+            .collect();
+        leaderboard.sort_by(|(_, a), (_, b)| b.points.total_cmp(&a.points));
+        let record = DailyRecord::load(profile);
+        Daily {
+            date,
+            paragraph,
+            scored: false,
+            attempted: record.attempt(date).is_some(),
+            streak: record.streak(date),
+            leaderboard,
+        }
+    }
+
+    /// Uses the attempt of the day of the profile, before it's typed so it can't be retried by
+    /// leaving it.
+    pub fn start_attempt(&mut self, profile: &str) -> Result<(), String> {
+        let mut record = DailyRecord::load(profile);
+        record.days.insert(
+            date_key(self.date),
+            DailyAttempt {
+                paragraph: paragraph_key(&self.paragraph),
+                score: None,
+            },
+        );
+        record.save(profile)?;
+        self.scored = true;
+        self.attempted = true;
+        Ok(())
+    }
+
+    /// Saves the score of the attempt of the day and updates the leaderboard and streak.
+    pub fn finish_attempt(&mut self, profile: &str, run: &TraceRun) -> Result<(), String> {
+        let mut record = DailyRecord::load(profile);
+        record.days.insert(
+            date_key(self.date),
+            DailyAttempt {
+                paragraph: paragraph_key(&self.paragraph),
+                score: Some(DailyScore::from_run(run)),
+            },
+        );
+        record.save(profile)?;
+        let updated = Daily::load(profile, self.date, self.paragraph.clone());
+        self.streak = updated.streak;
+        self.leaderboard = updated.leaderboard;
+        Ok(())
+    }
+
+    /// Place of the profile in the leaderboard, from 1.
+    pub fn rank(&self, profile: &str) -> Option<usize> {
+        self.leaderboard
+            .iter()
+            .position(|(name, _)| name == profile)
+            .map(|i| i + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn paragraphs(contents: &[&str]) -> Vec<AppParagraph> {
+        contents
+            .iter()
+            .map(|content| AppParagraph {
+                content: content.to_string(),
+                ..AppParagraph::new()
+            })
+            .collect()
+    }
+
+    fn record(days: &[(u32, bool)]) -> DailyRecord {
+        let days = days
+            .iter()
+            .map(|&(day, finished)| {
+                let attempt = DailyAttempt {
+                    paragraph: String::new(),
+                    score: finished.then(DailyScore::default),
+                };
+                (date_key(date(day)), attempt)
+            })
+            .collect();
+        DailyRecord { days }
+    }
+
+    #[test]
+    fn seed_is_the_same_for_a_date() {
+        let paragraphs = paragraphs(&["Uno", "Dos", "Tres"]);
+        let seed = daily_seed(date(1), &paragraphs);
+        assert_eq!(seed, daily_seed(date(1), &paragraphs));
+        // Saved streaks and leaderboards rely on it not changing between versions.
+        assert_eq!(seed, 0xfcd736a19296485b);
+    }
+
+    #[test]
+    fn seed_ignores_the_order_of_the_paragraphs() {
+        let seed = daily_seed(date(1), &paragraphs(&["Uno", "Dos", "Tres"]));
+        assert_eq!(
+            seed,
+            daily_seed(date(1), &paragraphs(&["Tres", "Uno", "Dos"]))
+        );
+    }
+
+    #[test]
+    fn seed_changes_with_the_date_and_the_paragraphs() {
+        let paragraphs_1 = paragraphs(&["Uno", "Dos", "Tres"]);
+        let seed = daily_seed(date(1), &paragraphs_1);
+        assert_ne!(seed, daily_seed(date(2), &paragraphs_1));
+        assert_ne!(seed, daily_seed(date(1), &paragraphs(&["Uno", "Dos"])));
+    }
+
+    #[test]
+    fn paragraph_is_the_same_for_a_date() {
+        let contents = ["Uno", "Dos", "Tres", "Cuatro", "Cinco"];
+        let paragraph = daily_paragraph(date(1), &paragraphs(&contents)).unwrap();
+        let mut reversed = contents;
+        reversed.reverse();
+        let again = daily_paragraph(date(1), &paragraphs(&reversed)).unwrap();
+        assert_eq!(paragraph.content, again.content);
+        assert!(daily_paragraph(date(1), &[]).is_none());
+    }
+
+    #[test]
+    fn streak_counts_days_in_a_row() {
+        assert_eq!(record(&[]).streak(date(10)), 0);
+        assert_eq!(
+            record(&[(8, true), (9, true), (10, true)]).streak(date(10)),
+            3
+        );
+    }
+
+    #[test]
+    fn streak_waits_for_today_to_end() {
+        let record = record(&[(8, true), (9, true)]);
+        assert_eq!(record.streak(date(10)), 2);
+        // A whole day without the challenge breaks it.
+        assert_eq!(record.streak(date(11)), 0);
+    }
+
+    #[test]
+    fn streak_stops_at_gaps() {
+        let record = record(&[(5, true), (6, true), (8, true), (9, true)]);
+        assert_eq!(record.streak(date(9)), 2);
+        assert_eq!(record.streak(date(6)), 2);
+        assert_eq!(record.streak(date(7)), 2);
+    }
+
+    #[test]
+    fn unfinished_attempts_break_the_streak() {
+        let record = record(&[(7, true), (8, false), (9, true), (10, false)]);
+        assert_eq!(record.streak(date(10)), 1);
+        assert_eq!(record.streak(date(8)), 1);
+    }
+}
@@ -0,0 +1,815 @@
+use crate::library::paragraph_key;
+use crate::{generate_all_chars, get_app_path, AppParagraph};
+use serde::Deserialize;
+use std::collections::{hash_map::Entry, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Directory with more databases, besides `database.csv`.
+const DATABASES_DIR: &str = "databases";
+/// The paragraphs of `database.csv` are in Spanish.
+pub const DEFAULT_LANGUAGE: &str = "es";
+
+/// Formats of the databases, picked by the extension of their files.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DatabaseFormat {
+    /// Rows with `title,author,date,content` and an optional `language`.
+    Csv,
+    /// An array with the paragraphs as objects, with the same fields as the CSV rows.
+    Json,
+    /// A paragraph object per line.
+    JsonLines,
+    /// A `[[paragraphs]]` table per paragraph.
+    Toml,
+    /// Paragraphs separated by blank lines, see [`parse_text`].
+    Text,
+    /// Like text, headings change the title of the paragraphs after them.
+    Markdown,
+}
+
+impl DatabaseFormat {
+    pub fn from_path(path: &Path) -> Option<DatabaseFormat> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "csv" => Some(DatabaseFormat::Csv),
+            "json" => Some(DatabaseFormat::Json),
+            "jsonl" => Some(DatabaseFormat::JsonLines),
+            "toml" => Some(DatabaseFormat::Toml),
+            "txt" => Some(DatabaseFormat::Text),
+            "md" | "markdown" => Some(DatabaseFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// `database.csv` followed by the databases of the `databases` directory, sorted by name.
+pub fn database_files() -> Vec<PathBuf> {
+    let mut files = vec![];
+    let main_database = get_app_path("database.csv");
+    if main_database.exists() {
+        files.push(main_database);
+    }
+    if let Ok(entries) = std::fs::read_dir(get_app_path(DATABASES_DIR)) {
+        let mut databases: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| DatabaseFormat::from_path(path).is_some())
+            .collect();
+        databases.sort();
+        files.extend(databases);
+    }
+    files
+}
+
+/// How bad a problem of a database is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IssueLevel {
+    /// The database can't be read, or has paragraphs that can't be typed.
+    Error,
+    /// Paragraphs that can be typed but are probably a mistake.
+    Warning,
+}
+
+/// A problem found in a database, at the line where the paragraph starts when it's known.
+#[derive(Clone, Debug)]
+pub struct DatabaseIssue {
+    pub level: IssueLevel,
+    pub file: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for DatabaseIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// Issues shown when the databases can't be loaded, the rest are left to `validate-db`.
+const MAX_SHOWN_ISSUES: usize = 5;
+/// Where the paragraphs skipped while loading the databases are listed.
+const DATABASE_LOG: &str = "database.log";
+
+/// Reads every paragraph of every database, skipping the paragraphs that can't be typed and
+/// listing them in `database.log`. Fails only when a database can't be read at all.
+pub fn load_paragraphs() -> Result<Vec<AppParagraph>, String> {
+    let mut files = database_files();
+    if files.is_empty() {
+        // Reports that the default database is missing.
+        files.push(get_app_path("database.csv"));
+    }
+    let mut paragraphs = vec![];
+    let mut errors = vec![];
+    let mut skipped = vec![];
+    for file in files {
+        match read_database(&file) {
+            Ok(database) => {
+                let name = database_name(&file);
+                for (line, paragraph) in database {
+                    let issues: Vec<DatabaseIssue> = paragraph_issues(&name, line, &paragraph)
+                        .into_iter()
+                        .filter(|issue| issue.level == IssueLevel::Error)
+                        .collect();
+                    if issues.is_empty() {
+                        paragraphs.push(paragraph);
+                    } else {
+                        skipped.extend(issues);
+                    }
+                }
+            }
+            Err(issue) => errors.push(issue),
+        }
+    }
+    log_skipped_paragraphs(&skipped);
+    if errors.is_empty() {
+        return Ok(paragraphs);
+    }
+    let mut report: Vec<String> = errors
+        .iter()
+        .take(MAX_SHOWN_ISSUES)
+        .map(ToString::to_string)
+        .collect();
+    if errors.len() > MAX_SHOWN_ISSUES {
+        report.push(format!("And {} more", errors.len() - MAX_SHOWN_ISSUES));
+    }
+    report.push("Run trace-game validate-db to check the databases".to_string());
+    Err(report.join("\n"))
+}
+
+/// Lists the skipped paragraphs in `database.log`, removing it when there are none. The log is
+/// a convenience, so failing to write it doesn't stop the game.
+fn log_skipped_paragraphs(skipped: &[DatabaseIssue]) {
+    let path = get_app_path(DATABASE_LOG);
+    if skipped.is_empty() {
+        let _ = std::fs::remove_file(path);
+        return;
+    }
+    let mut log: Vec<String> = vec!["Skipped paragraphs:".to_string()];
+    log.extend(skipped.iter().map(ToString::to_string));
+    log.push("Run trace-game validate-db to check the databases".to_string());
+    let _ = std::fs::write(path, log.join("\n") + "\n");
+}
+
+/// Checks every database, returning all their problems.
+pub fn validate_databases() -> Vec<DatabaseIssue> {
+    let mut files = database_files();
+    if files.is_empty() {
+        files.push(get_app_path("database.csv"));
+    }
+    let mut issues = vec![];
+    // Where each paragraph was first seen, to find the repeated ones.
+    let mut seen: HashMap<String, (String, usize)> = HashMap::new();
+    for file in files {
+        let name = database_name(&file);
+        let database = match read_database(&file) {
+            Ok(database) => database,
+            Err(issue) => {
+                issues.push(issue);
+                continue;
+            }
+        };
+        for (line, paragraph) in database {
+            issues.extend(paragraph_issues(&name, line, &paragraph));
+            match seen.entry(paragraph_key(&paragraph)) {
+                Entry::Occupied(first) => {
+                    let (first_file, first_line) = first.get();
+                    issues.push(DatabaseIssue {
+                        level: IssueLevel::Warning,
+                        file: name.clone(),
+                        line: Some(line),
+                        message: format!("repeats the paragraph of {}:{}", first_file, first_line),
+                    });
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((name.clone(), line));
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// Problems of a paragraph that starts at `line` of the database `file`.
+fn paragraph_issues(file: &str, line: usize, paragraph: &AppParagraph) -> Vec<DatabaseIssue> {
+    let issue = |level: IssueLevel, message: String| DatabaseIssue {
+        level,
+        file: file.to_string(),
+        line: Some(line),
+        message,
+    };
+    let mut issues = vec![];
+    if paragraph.content.trim().is_empty() {
+        issues.push(issue(IssueLevel::Error, "the content is empty".to_string()));
+    }
+    let typeable = generate_all_chars();
+    let mut untypeable: Vec<char> = paragraph
+        .content
+        .chars()
+        .filter(|c| !typeable.contains(c))
+        .collect();
+    untypeable.sort_unstable();
+    untypeable.dedup();
+    if !untypeable.is_empty() {
+        let chars: Vec<String> = untypeable.iter().map(|c| format!("{:?}", c)).collect();
+        issues.push(issue(
+            IssueLevel::Error,
+            format!("chars that can't be typed: {}", chars.join(", ")),
+        ));
+    }
+    for (field, value) in [
+        ("title", &paragraph.title),
+        ("author", &paragraph.author),
+        ("date", &paragraph.date),
+    ] {
+        if value.trim().is_empty() {
+            issues.push(issue(
+                IssueLevel::Warning,
+                format!("the {} is missing", field),
+            ));
+        }
+    }
+    issues
+}
+
+/// Path of a database as shown in errors, relative to the app directory.
+pub fn database_name(path: &Path) -> String {
+    let app_dir = get_app_path("");
+    path.strip_prefix(&app_dir)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Name of the file without its extension and language, like `news` for `news.en.md`.
+fn file_title(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    stem.split('.').next().unwrap_or_default().to_string()
+}
+
+/// Language of the paragraphs of a database without a `language` field, taken from names like
+/// `news.en.csv`.
+fn file_language(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.rsplit_once('.'))
+        .map(|(_, language)| language.to_lowercase())
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string())
+}
+
+/// Reads the paragraphs of a database with the line where each one starts.
+fn read_database(path: &Path) -> Result<Vec<(usize, AppParagraph)>, DatabaseIssue> {
+    let name = database_name(path);
+    let error = |line: Option<usize>, message: String| DatabaseIssue {
+        level: IssueLevel::Error,
+        file: name.clone(),
+        line,
+        message,
+    };
+    let format = DatabaseFormat::from_path(path)
+        .ok_or_else(|| error(None, "unknown database format".to_string()))?;
+    let bytes = std::fs::read(path).map_err(|err| error(None, err.to_string()))?;
+    let content = String::from_utf8(bytes).map_err(|err| {
+        let valid = &err.as_bytes()[..err.utf8_error().valid_up_to()];
+        let line = valid.iter().filter(|byte| **byte == b'\n').count() + 1;
+        error(
+            Some(line),
+            "the text isn't UTF-8, save the file with that encoding".to_string(),
+        )
+    })?;
+    let mut paragraphs = parse_database(&content, format, &file_title(path))
+        .map_err(|(line, message)| error(line, message))?;
+    let language = file_language(path);
+    for (_, paragraph) in &mut paragraphs {
+        paragraph.language = match paragraph.language.trim() {
+            "" => language.clone(),
+            tag => tag.to_lowercase(),
+        };
+    }
+    Ok(paragraphs)
+}
+
+/// Why a database can't be parsed, with the line where it happened when it's known.
+type ParseError = (Option<usize>, String);
+
+#[derive(Deserialize)]
+struct TomlDatabase {
+    #[serde(default)]
+    paragraphs: Vec<AppParagraph>,
+}
+
+/// Reads the paragraphs of a database with the line where each one starts. `title` is used for
+/// the text paragraphs without one.
+pub fn parse_database(
+    content: &str, format: DatabaseFormat, title: &str,
+) -> Result<Vec<(usize, AppParagraph)>, ParseError> {
+    match format {
+        DatabaseFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(content.as_bytes());
+            let headers = reader
+                .headers()
+                .map_err(|err| (Some(1), err.to_string()))?
+                .clone();
+            reader
+                .records()
+                .map(|record| {
+                    let record = record.map_err(|err| {
+                        let line = err.position().map(|position| position.line() as usize);
+                        (line, err.to_string())
+                    })?;
+                    let line = record
+                        .position()
+                        .map_or(0, |position| position.line() as usize);
+                    let paragraph = record
+                        .deserialize(Some(&headers))
+                        .map_err(|err| (Some(line), err.to_string()))?;
+                    Ok((line, paragraph))
+                })
+                .collect()
+        }
+        DatabaseFormat::Json => {
+            let paragraphs: Vec<AppParagraph> =
+                serde_json::from_str(content).map_err(|err| (Some(err.line()), err.to_string()))?;
+            let lines = json_object_lines(content);
+            Ok(paragraphs
+                .into_iter()
+                .enumerate()
+                .map(|(i, paragraph)| (lines.get(i).copied().unwrap_or(1), paragraph))
+                .collect())
+        }
+        DatabaseFormat::JsonLines => content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map(|paragraph| (i + 1, paragraph))
+                    .map_err(|err| (Some(i + 1), err.to_string()))
+            })
+            .collect(),
+        DatabaseFormat::Toml => {
+            let database: TomlDatabase = toml::from_str(content).map_err(|err| {
+                let line = err.line_col().map(|(line, _)| line + 1);
+                (line, err.to_string())
+            })?;
+            let lines: Vec<usize> = content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.trim().starts_with("[[paragraphs]]"))
+                .map(|(i, _)| i + 1)
+                .collect();
+            Ok(database
+                .paragraphs
+                .into_iter()
+                .enumerate()
+                .map(|(i, paragraph)| (lines.get(i).copied().unwrap_or(1), paragraph))
+                .collect())
+        }
+        DatabaseFormat::Text => Ok(parse_text(content, title, false)),
+        DatabaseFormat::Markdown => Ok(parse_text(content, title, true)),
+    }
+}
+
+/// Lines where the objects of a JSON array start.
+fn json_object_lines(content: &str) -> Vec<usize> {
+    let mut lines = vec![];
+    let mut line = 1;
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in content.chars() {
+        match c {
+            '\n' => line += 1,
+            _ if in_string => match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            },
+            '"' => in_string = true,
+            '[' | '{' => {
+                if c == '{' && depth == 1 {
+                    lines.push(line);
+                }
+                depth += 1;
+            }
+            ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    lines
+}
+
+/// Splits a text in paragraphs by its blank lines, the lines of a paragraph are joined with
+/// spaces. The text can start with a front matter setting the `title`, `author`, `date` and
+/// `language` of every paragraph:
+///
+/// ```text
+/// ---
+/// title: Un lugar para Karli
+/// author: Achieve 3000
+/// ---
+/// ```
+///
+/// In Markdown the headings aren't paragraphs, they're the title of the paragraphs after them.
+/// A front matter without its closing `---` is read as a paragraph.
+fn parse_text(content: &str, title: &str, markdown: bool) -> Vec<(usize, AppParagraph)> {
+    let mut metadata = AppParagraph {
+        title: title.to_string(),
+        ..AppParagraph::new()
+    };
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .peekable();
+    // Without the closing line it isn't a front matter, but a paragraph.
+    let has_front_matter = lines.peek().map(|(_, line)| *line) == Some("---")
+        && content.lines().skip(1).any(|line| line.trim() == "---");
+    if has_front_matter {
+        lines.next();
+        for (_, line) in lines.by_ref() {
+            if line == "---" {
+                break;
+            }
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim().trim_matches('"').to_string()),
+                None => continue,
+            };
+            match key {
+                "title" => metadata.title = value,
+                "author" => metadata.author = value,
+                "date" => metadata.date = value,
+                "language" => metadata.language = value,
+                _ => {}
+            }
+        }
+    }
+
+    let mut paragraphs = vec![];
+    let mut title = metadata.title.clone();
+    // Line where the current paragraph starts, and its lines.
+    let mut current: (usize, Vec<&str>) = (0, vec![]);
+    let mut push_paragraph = |current: &mut (usize, Vec<&str>), title: &str| {
+        if !current.1.is_empty() {
+            paragraphs.push((
+                current.0,
+                AppParagraph {
+                    content: current.1.join(" "),
+                    title: title.to_string(),
+                    ..metadata.clone()
+                },
+            ));
+            current.1.clear();
+        }
+    };
+    for (number, line) in lines {
+        if line.is_empty() {
+            push_paragraph(&mut current, &title);
+        } else if let Some(heading) = markdown.then(|| markdown_heading(line)).flatten() {
+            push_paragraph(&mut current, &title);
+            // An empty heading goes back to the title of the file.
+            title = if heading.is_empty() {
+                metadata.title.clone()
+            } else {
+                heading.to_string()
+            };
+        } else {
+            if current.1.is_empty() {
+                current.0 = number;
+            }
+            current.1.push(line);
+        }
+    }
+    push_paragraph(&mut current, &title);
+    paragraphs
+}
+
+/// Text of a Markdown heading, like `## Title` or `# Title #`. Like in CommonMark, the `#` have to
+/// be followed by a space, so lines like `#hashtag` or `#1 in sales` are text.
+fn markdown_heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    if !(1..=6).contains(&level) || !(text.is_empty() || text.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let text = text.trim();
+    // The closing `#` are only part of the heading when they're apart from the text.
+    let without_closing = text.trim_end_matches('#');
+    if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        Some(without_closing.trim_end())
+    } else {
+        Some(text)
+    }
+}
+
+/// Languages of the paragraphs, sorted.
+pub fn languages(paragraphs: &[AppParagraph]) -> Vec<String> {
+    let languages: BTreeSet<&String> = paragraphs
+        .iter()
+        .map(|paragraph| &paragraph.language)
+        .collect();
+    languages.into_iter().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(content: &str) -> AppParagraph {
+        AppParagraph {
+            content: content.to_string(),
+            title: "Title".to_string(),
+            author: "Author".to_string(),
+            date: "2019".to_string(),
+            language: "es".to_string(),
+        }
+    }
+
+    fn contents(paragraphs: &[(usize, AppParagraph)]) -> Vec<(usize, &str)> {
+        paragraphs
+            .iter()
+            .map(|(line, paragraph)| (*line, paragraph.content.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn formats_are_picked_by_extension() {
+        let format = |path: &str| DatabaseFormat::from_path(Path::new(path));
+        assert_eq!(format("news.CSV"), Some(DatabaseFormat::Csv));
+        assert_eq!(format("news.en.jsonl"), Some(DatabaseFormat::JsonLines));
+        assert_eq!(format("news.markdown"), Some(DatabaseFormat::Markdown));
+        assert_eq!(format("news.pdf"), None);
+        assert_eq!(format("news"), None);
+    }
+
+    #[test]
+    fn file_names_give_the_title_and_language() {
+        assert_eq!(file_title(Path::new("databases/news.en.md")), "news");
+        assert_eq!(file_language(Path::new("databases/news.EN.md")), "en");
+        assert_eq!(
+            file_language(Path::new("databases/news.md")),
+            DEFAULT_LANGUAGE
+        );
+    }
+
+    #[test]
+    fn parses_csv() {
+        let content = "\
+title,author,date,content,language
+A,B,C,\"Hola, mundo\",es
+D,E,F,\"Dos
+líneas\",en
+";
+        let paragraphs = parse_database(content, DatabaseFormat::Csv, "file").unwrap();
+        assert_eq!(
+            contents(&paragraphs),
+            vec![(2, "Hola, mundo"), (3, "Dos\nlíneas")]
+        );
+        assert_eq!(paragraphs[1].1.language, "en");
+        assert_eq!(paragraphs[0].1.title, "A");
+    }
+
+    #[test]
+    fn csv_without_language_column_is_read() {
+        let content = "title,author,date,content\nA,B,C,Hola\n";
+        let paragraphs = parse_database(content, DatabaseFormat::Csv, "file").unwrap();
+        assert_eq!(paragraphs[0].1.language, "");
+    }
+
+    #[test]
+    fn csv_errors_have_their_line() {
+        let content = "title,author,date,content\nA,B,C,Hola\nA,B\n";
+        let (line, _) = parse_database(content, DatabaseFormat::Csv, "file")
+            .err()
+            .unwrap();
+        assert_eq!(line, Some(3));
+    }
+
+    #[test]
+    fn parses_json() {
+        let content = r#"[
+  {"title": "A", "author": "B", "date": "C", "content": "Uno {con} [llaves]"},
+  {
+    "title": "D\"", "author": "E", "date": "F",
+    "content": "Dos", "language": "en"
+  }
+]"#;
+        let paragraphs = parse_database(content, DatabaseFormat::Json, "file").unwrap();
+        assert_eq!(
+            contents(&paragraphs),
+            vec![(2, "Uno {con} [llaves]"), (3, "Dos")]
+        );
+        assert_eq!(paragraphs[1].1.title, "D\"");
+        assert_eq!(paragraphs[1].1.language, "en");
+    }
+
+    #[test]
+    fn json_errors_have_their_line() {
+        let content = "[\n  {\"title\": \"A\"}\n]";
+        let (line, _) = parse_database(content, DatabaseFormat::Json, "file")
+            .err()
+            .unwrap();
+        assert_eq!(line, Some(2));
+    }
+
+    #[test]
+    fn json_object_lines_skip_strings() {
+        let content = "[\n{\"a\": \"{\\\"[\"},\n\n{\"b\": {\"c\": 1}}]";
+        assert_eq!(json_object_lines(content), vec![2, 4]);
+    }
+
+    #[test]
+    fn parses_json_lines() {
+        let content = "\
+{\"title\": \"A\", \"author\": \"B\", \"date\": \"C\", \"content\": \"Uno\"}
+
+{\"title\": \"D\", \"author\": \"E\", \"date\": \"F\", \"content\": \"Dos\"}
+";
+        let paragraphs = parse_database(content, DatabaseFormat::JsonLines, "file").unwrap();
+        assert_eq!(contents(&paragraphs), vec![(1, "Uno"), (3, "Dos")]);
+        let (line, _) = parse_database("{}\nnot json\n", DatabaseFormat::JsonLines, "file")
+            .err()
+            .unwrap();
+        assert_eq!(line, Some(1));
+    }
+
+    #[test]
+    fn parses_toml() {
+        let content = r#"
+[[paragraphs]]
+title = "A"
+author = "B"
+date = "C"
+content = "Uno"
+
+[[paragraphs]]
+title = "D"
+author = "E"
+date = "F"
+content = "Dos"
+language = "en"
+"#;
+        let paragraphs = parse_database(content, DatabaseFormat::Toml, "file").unwrap();
+        assert_eq!(contents(&paragraphs), vec![(2, "Uno"), (8, "Dos")]);
+        assert_eq!(paragraphs[1].1.language, "en");
+        assert!(parse_database("", DatabaseFormat::Toml, "file")
+            .unwrap()
+            .is_empty());
+        let (line, _) = parse_database("[[paragraphs]]\ntitle = \n", DatabaseFormat::Toml, "file")
+            .err()
+            .unwrap();
+        assert_eq!(line, Some(2));
+    }
+
+    #[test]
+    fn parses_text() {
+        let content = "Uno\ndos\n\n\n  Tres  \n";
+        let paragraphs = parse_database(content, DatabaseFormat::Text, "file").unwrap();
+        assert_eq!(contents(&paragraphs), vec![(1, "Uno dos"), (5, "Tres")]);
+        assert_eq!(paragraphs[0].1.title, "file");
+        assert_eq!(paragraphs[0].1.author, "");
+    }
+
+    #[test]
+    fn text_headings_are_paragraphs() {
+        let paragraphs = parse_database("# Uno\nDos", DatabaseFormat::Text, "file").unwrap();
+        assert_eq!(contents(&paragraphs), vec![(1, "# Uno Dos")]);
+    }
+
+    #[test]
+    fn markdown_headings_are_titles() {
+        let content = "Antes\n# Uno\nDos\n## Tres\n\nCuatro\n";
+        let paragraphs = parse_database(content, DatabaseFormat::Markdown, "file").unwrap();
+        assert_eq!(
+            contents(&paragraphs),
+            vec![(1, "Antes"), (3, "Dos"), (6, "Cuatro")]
+        );
+        let titles: Vec<&str> = paragraphs.iter().map(|(_, p)| p.title.as_str()).collect();
+        assert_eq!(titles, vec!["file", "Uno", "Tres"]);
+    }
+
+    #[test]
+    fn markdown_headings_need_a_space() {
+        let content =
+            "#hashtag\n#1 in sales\n\n####### Siete\n\n### Tres ###\n# C# #\nUno\n#\nDos\n";
+        let paragraphs = parse_database(content, DatabaseFormat::Markdown, "file").unwrap();
+        assert_eq!(
+            contents(&paragraphs),
+            vec![
+                (1, "#hashtag #1 in sales"),
+                (4, "####### Siete"),
+                (8, "Uno"),
+                (10, "Dos")
+            ]
+        );
+        let titles: Vec<&str> = paragraphs.iter().map(|(_, p)| p.title.as_str()).collect();
+        assert_eq!(titles, vec!["file", "file", "C#", "file"]);
+    }
+
+    #[test]
+    fn front_matter_sets_the_metadata() {
+        let content = "\
+---
+title: \"Un lugar: para Karli\"
+author: Achieve 3000
+date: 17 de junio de 2019
+language: en
+unknown: ignored
+not a pair
+---
+Uno
+";
+        let paragraphs = parse_database(content, DatabaseFormat::Text, "file").unwrap();
+        assert_eq!(contents(&paragraphs), vec![(9, "Uno")]);
+        let paragraph = &paragraphs[0].1;
+        assert_eq!(paragraph.title, "Un lugar: para Karli");
+        assert_eq!(paragraph.author, "Achieve 3000");
+        assert_eq!(paragraph.date, "17 de junio de 2019");
+        assert_eq!(paragraph.language, "en");
+    }
+
+    #[test]
+    fn markdown_headings_replace_the_front_matter_title() {
+        let content = "---\ntitle: Libro\nauthor: A\n---\nUno\n# Capítulo\nDos\n";
+        let paragraphs = parse_database(content, DatabaseFormat::Markdown, "file").unwrap();
+        let titles: Vec<&str> = paragraphs.iter().map(|(_, p)| p.title.as_str()).collect();
+        assert_eq!(titles, vec!["Libro", "Capítulo"]);
+        assert!(paragraphs.iter().all(|(_, p)| p.author == "A"));
+    }
+
+    #[test]
+    fn empty_front_matter_is_skipped() {
+        let paragraphs = parse_database("---\n---\nUno", DatabaseFormat::Text, "file").unwrap();
+        assert_eq!(contents(&paragraphs), vec![(3, "Uno")]);
+        assert_eq!(paragraphs[0].1.title, "file");
+    }
+
+    #[test]
+    fn front_matter_must_be_closed_and_first() {
+        let paragraphs =
+            parse_database("---\ntitle: A\nUno", DatabaseFormat::Text, "file").unwrap();
+        assert_eq!(contents(&paragraphs), vec![(1, "--- title: A Uno")]);
+        let paragraphs =
+            parse_database("Uno\n---\ntitle: A\n---", DatabaseFormat::Text, "file").unwrap();
+        assert_eq!(paragraphs.len(), 1);
+        assert_eq!(paragraphs[0].1.title, "file");
+    }
+
+    #[test]
+    fn typeable_paragraphs_have_no_issues() {
+        assert!(paragraph_issues("db.csv", 2, &paragraph("¿Hola, mundo?")).is_empty());
+    }
+
+    #[test]
+    fn empty_content_is_an_error() {
+        let issues = paragraph_issues("db.csv", 2, &paragraph("  "));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Error);
+        assert_eq!(issues[0].to_string(), "db.csv:2: the content is empty");
+    }
+
+    #[test]
+    fn untypeable_chars_are_an_error() {
+        let issues = paragraph_issues("db.csv", 3, &paragraph("Uno… dos — tres…"));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, IssueLevel::Error);
+        assert_eq!(issues[0].message, "chars that can't be typed: '—', '…'");
+    }
+
+    #[test]
+    fn missing_metadata_is_a_warning() {
+        let paragraph = AppParagraph {
+            title: " ".to_string(),
+            date: String::new(),
+            ..paragraph("Hola")
+        };
+        let issues = paragraph_issues("db.csv", 4, &paragraph);
+        let messages: Vec<(IssueLevel, &str)> = issues
+            .iter()
+            .map(|issue| (issue.level, issue.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (IssueLevel::Warning, "the title is missing"),
+                (IssueLevel::Warning, "the date is missing"),
+            ]
+        );
+    }
+
+    #[test]
+    fn issues_without_line_show_only_the_file() {
+        let issue = DatabaseIssue {
+            level: IssueLevel::Error,
+            file: "db.txt".to_string(),
+            line: None,
+            message: "unknown database format".to_string(),
+        };
+        assert_eq!(issue.to_string(), "db.txt: unknown database format");
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+/// Paragraphs this long or longer get the whole length part of the score.
+const LONG_PARAGRAPH: f64 = 600.0;
+
+/// Pairs of letters common in Spanish and English. The rest are slower to type.
+const COMMON_BIGRAMS: [&str; 60] = [
+    "de", "es", "en", "el", "la", "os", "ar", "er", "ra", "re", "on", "an", "as", "ad", "al", "or",
+    "nt", "ue", "qu", "co", "ta", "st", "te", "do", "ci", "to", "se", "ie", "io", "ro", "ca", "ll",
+    "th", "he", "in", "ha", "ou", "it", "ng", "ed", "nd", "at", "ti", "is", "et", "ve", "me", "ma",
+    "no", "po", "mo", "ri", "di", "ac", "ne", "na", "pa", "tr", "ic", "un",
+];
+
+/// Scores how hard a paragraph is to type, from 0 to 100. It adds up, with these weights:
+///
+/// - 30: the length, up to 600 chars.
+/// - 20: punctuation and symbols, full at one every 10 chars.
+/// - 15: accented letters, full at one every 20 chars.
+/// - 15: pairs of letters that aren't common.
+/// - 10: capital letters, full at one every 20 chars.
+/// - 10: numbers, full at one every 20 chars.
+pub fn difficulty_score(content: &str) -> f64 {
+    let chars: Vec<char> = content.chars().collect();
+    if chars.is_empty() {
+        return 0.0;
+    }
+    let count = chars.len() as f64;
+    let density =
+        |matches: fn(&char) -> bool| chars.iter().filter(|c| matches(c)).count() as f64 / count;
+    let punctuation = density(|c| !c.is_alphanumeric() && !c.is_whitespace());
+    let accents = density(|c| c.is_alphabetic() && !c.is_ascii());
+    let capitals = density(|c| c.is_uppercase());
+    let numbers = density(|c| c.is_numeric());
+
+    let lowercase: Vec<char> = content.to_lowercase().chars().collect();
+    let bigrams: Vec<String> = lowercase
+        .windows(2)
+        .filter(|pair| pair[0].is_alphabetic() && pair[1].is_alphabetic())
+        .map(|pair| pair.iter().collect())
+        .collect();
+    let uncommon = if bigrams.is_empty() {
+        0.0
+    } else {
+        bigrams
+            .iter()
+            .filter(|bigram| !COMMON_BIGRAMS.contains(&bigram.as_str()))
+            .count() as f64
+            / bigrams.len() as f64
+    };
+
+    30.0 * (count / LONG_PARAGRAPH).min(1.0)
+        + 20.0 * (punctuation * 10.0).min(1.0)
+        + 15.0 * (accents * 20.0).min(1.0)
+        + 15.0 * uncommon
+        + 10.0 * (capitals * 20.0).min(1.0)
+        + 10.0 * (numbers * 20.0).min(1.0)
+}
+
+/// Levels of difficulty the player can pick. In the default database there are a few more
+/// medium paragraphs than easy or hard ones.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    pub fn from_score(score: f64) -> Difficulty {
+        if score < 32.0 {
+            Difficulty::Easy
+        } else if score < 40.0 {
+            Difficulty::Medium
+        } else {
+            Difficulty::Hard
+        }
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_score(content: &str, expected: f64) {
+        let score = difficulty_score(content);
+        assert!(
+            (score - expected).abs() < 1e-9,
+            "{:?} scored {}",
+            content,
+            score
+        );
+    }
+
+    #[test]
+    fn scores_known_paragraphs() {
+        assert_score("", 0.0);
+        // Only the length, the pairs are common.
+        assert_score("de la", 0.25);
+        // "aa" isn't a common pair.
+        assert_score("aaaa", 15.2);
+        // Long, with punctuation, accents, capitals and numbers at their full weight.
+        assert_score(&"Á1,".repeat(200), 85.0);
+    }
+
+    #[test]
+    fn levels_start_at_their_boundaries() {
+        assert_eq!(Difficulty::from_score(0.0), Difficulty::Easy);
+        assert_eq!(Difficulty::from_score(31.99), Difficulty::Easy);
+        assert_eq!(Difficulty::from_score(32.0), Difficulty::Medium);
+        assert_eq!(Difficulty::from_score(39.99), Difficulty::Medium);
+        assert_eq!(Difficulty::from_score(40.0), Difficulty::Hard);
+        assert_eq!(Difficulty::from_score(100.0), Difficulty::Hard);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::timeline::Timeline;
+use crate::{source_label, TraceRun};
+use chrono::prelude::*;
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            _ => None,
+        }
+    }
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// A run as it's written in exports, with the metrics that can be derived from it.
+#[derive(Serialize)]
+struct ExportedRun<'a> {
+    /// Number of the run in the history, the same shown in the statistics window.
+    run: usize,
+    date: Option<DateTime<Utc>>,
+    title: &'a str,
+    wpm: f64,
+    accuracy: f64,
+    total_points: f64,
+    seconds: f64,
+    characters: usize,
+    /// Characters per minute.
+    cpm: f64,
+    /// Estimated from the accuracy, runs don't store the errors.
+    errors: usize,
+    source: &'a str,
+    language: &'a str,
+    difficulty: Option<f64>,
+    mode: &'a str,
+    failed: bool,
+    /// Chars typed right and errors of every second.
+    timeline: &'a Timeline,
+}
+
+impl<'a> ExportedRun<'a> {
+    fn new(number: usize, run: &'a TraceRun) -> ExportedRun<'a> {
+        let cpm = if run.seconds > 0.0 {
+            run.characters as f64 / run.seconds * 60.0
+        } else {
+            0.0
+        };
+        ExportedRun {
+            run: number,
+            date: run.date,
+            title: &run.title,
+            wpm: run.wpm,
+            accuracy: run.accuracy,
+            total_points: run.total_points,
+            seconds: run.seconds,
+            characters: run.characters,
+            cpm,
+            errors: (run.characters as f64 * (1.0 - run.accuracy)).round() as usize,
+            source: source_label(&run.source),
+            language: &run.language,
+            difficulty: run.difficulty,
+            mode: &run.mode,
+            failed: run.failed,
+            timeline: &run.timeline,
+        }
+    }
+}
+
+/// Indexes of the runs done on `since` or later, in local time. Runs without a date are only
+/// kept when there's no `since`.
+pub fn runs_since(runs: &[TraceRun], since: Option<NaiveDate>) -> Vec<usize> {
+    (0..runs.len())
+        .filter(|i| match since {
+            None => true,
+            Some(since) => runs[*i]
+                .date
+                .is_some_and(|date| date.with_timezone(&Local).naive_local().date() >= since),
+        })
+        .collect()
+}
+
+/// Writes the runs at `indexes` to `writer`.
+pub fn export_runs(
+    runs: &[TraceRun], indexes: &[usize], format: ExportFormat, mut writer: impl Write,
+) -> Result<(), String> {
+    let exported: Vec<ExportedRun> = indexes
+        .iter()
+        .map(|i| ExportedRun::new(*i, &runs[*i]))
+        .collect();
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &exported).map_err(|err| err.to_string())?;
+            writeln!(writer).map_err(|err| err.to_string())
+        }
+        ExportFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            for run in &exported {
+                csv_writer.serialize(run).map_err(|err| err.to_string())?;
+            }
+            csv_writer.flush().map_err(|err| err.to_string())
+        }
+        ExportFormat::Markdown => write_markdown(&exported, writer).map_err(|err| err.to_string()),
+    }
+}
+
+fn write_markdown(runs: &[ExportedRun], mut writer: impl Write) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "| # | Date | Paragraph | WPM | Accuracy | Points | Seconds | Characters | CPM | Errors \
+         | Source | Language | Difficulty | Mode |"
+    )?;
+    writeln!(
+        writer,
+        "|---|---|---|--:|--:|--:|--:|--:|--:|--:|---|---|--:|---|"
+    )?;
+    for run in runs {
+        let date = run
+            .date
+            .map(|date| {
+                date.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        writeln!(
+            writer,
+            "| {} | {} | {} | {:.2} | {:.2} % | {:.2} | {:.2} | {} | {:.2} | {} | {} | {} | {} | {} |",
+            run.run,
+            date,
+            run.title.replace('|', "\\|"),
+            run.wpm,
+            run.accuracy * 100.0,
+            run.total_points,
+            run.seconds,
+            run.characters,
+            run.cpm,
+            run.errors,
+            run.source,
+            run.language,
+            run.difficulty
+                .map(|difficulty| format!("{:.1}", difficulty))
+                .unwrap_or_default(),
+            match (run.mode, run.failed) {
+                ("", true) => "failed".to_string(),
+                (mode, true) => format!("{}, failed", mode),
+                (mode, false) => mode.to_string(),
+            },
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,405 @@
+use crate::timeline::Timeline;
+use crate::{calculate_points, get_track_record, save_track_record, TraceRun};
+use chrono::prelude::*;
+use std::io::Read;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImportFormat {
+    /// The CSV file downloaded from the account page of Monkeytype.
+    Monkeytype,
+    /// Any CSV file, its columns are picked with a [`ColumnMapping`].
+    Csv,
+}
+
+impl ImportFormat {
+    pub fn from_name(name: &str) -> Option<ImportFormat> {
+        match name.to_lowercase().as_str() {
+            "monkeytype" => Some(ImportFormat::Monkeytype),
+            "csv" => Some(ImportFormat::Csv),
+            _ => None,
+        }
+    }
+    /// Source the imported runs are tagged with when none is given.
+    pub fn default_source(self) -> &'static str {
+        match self {
+            ImportFormat::Monkeytype => "monkeytype",
+            ImportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Columns of a generic CSV file that hold each field of the runs. Only `wpm` and `accuracy` are
+/// required, by default every field is read from the column with its name, like in the files
+/// written by `stats export`.
+#[derive(Clone, Debug)]
+pub struct ColumnMapping {
+    wpm: String,
+    accuracy: String,
+    /// The accuracies are percentages, like 97, instead of fractions, like 0.97. Values ending
+    /// in `%` are read as percentages anyway.
+    accuracy_percent: bool,
+    seconds: Option<String>,
+    date: Option<String>,
+    title: Option<String>,
+    characters: Option<String>,
+    language: Option<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        ColumnMapping {
+            wpm: "wpm".to_string(),
+            accuracy: "accuracy".to_string(),
+            accuracy_percent: false,
+            seconds: Some("seconds".to_string()),
+            date: Some("date".to_string()),
+            title: Some("title".to_string()),
+            characters: Some("characters".to_string()),
+            language: Some("language".to_string()),
+        }
+    }
+}
+
+impl ColumnMapping {
+    /// Parses mappings like `wpm=Speed,accuracy=Acc,date=Timestamp`. Fields that aren't
+    /// mentioned keep their default column. `accuracy_scale=percent` or `fraction` tells how the
+    /// accuracies are written.
+    pub fn parse(value: &str) -> Result<ColumnMapping, String> {
+        let mut mapping = ColumnMapping::default();
+        for pair in value.split(',') {
+            let (field, column) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid mapping \"{}\", use field=column", pair))?;
+            let column = column.trim().to_string();
+            match field.trim() {
+                "wpm" => mapping.wpm = column,
+                "accuracy" => mapping.accuracy = column,
+                "accuracy_scale" => {
+                    mapping.accuracy_percent = match column.as_str() {
+                        "percent" => true,
+                        "fraction" => false,
+                        _ => {
+                            return Err(format!(
+                                "Unknown accuracy scale \"{}\", use percent or fraction",
+                                column
+                            ))
+                        }
+                    }
+                }
+                "seconds" => mapping.seconds = Some(column),
+                "date" => mapping.date = Some(column),
+                "title" => mapping.title = Some(column),
+                "characters" => mapping.characters = Some(column),
+                "language" => mapping.language = Some(column),
+                field => {
+                    return Err(format!(
+                        "Unknown field \"{}\", the fields are wpm, accuracy, \
+                         accuracy_scale, seconds, date, title, characters and language",
+                        field
+                    ))
+                }
+            }
+        }
+        Ok(mapping)
+    }
+}
+
+/// Reads the runs of a file exported by another tool, tagged with `source`.
+pub fn read_runs(
+    reader: impl Read, format: ImportFormat, mapping: &ColumnMapping, source: &str,
+) -> Result<Vec<TraceRun>, String> {
+    let mut csv_reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers = csv_reader.headers().map_err(|err| err.to_string())?.clone();
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let required_column =
+        |name: &str| column(name).ok_or_else(|| format!("The file has no \"{}\" column", name));
+
+    let mut runs = Vec::new();
+    for (line, record) in csv_reader.records().enumerate() {
+        let record = record.map_err(|err| err.to_string())?;
+        // The header is the first line.
+        let line = line + 2;
+        let field = |index: Option<usize>| index.and_then(|i| record.get(i)).unwrap_or("").trim();
+        let number = |index: Option<usize>, name: &str| -> Result<f64, String> {
+            let value = field(index);
+            if value.is_empty() {
+                return Ok(0.0);
+            }
+            value
+                .trim_end_matches('%')
+                .parse::<f64>()
+                .map_err(|_| format!("Line {}: invalid {} \"{}\"", line, name, value))
+        };
+        let date = |index: Option<usize>| -> Result<Option<DateTime<Utc>>, String> {
+            let value = field(index);
+            if value.is_empty() {
+                return Ok(None);
+            }
+            parse_date(value)
+                .map(Some)
+                .ok_or_else(|| format!("Line {}: invalid date \"{}\"", line, value))
+        };
+
+        let run = match format {
+            ImportFormat::Monkeytype => {
+                let wpm = number(Some(required_column("wpm")?), "wpm")?;
+                let accuracy = number(Some(required_column("acc")?), "accuracy")? / 100.0;
+                // Correct, incorrect, extra and missed chars, separated by ';'.
+                let characters = field(column("charStats"))
+                    .split(';')
+                    .enumerate()
+                    .filter(|(i, _)| *i != 2)
+                    .filter_map(|(_, count)| count.parse::<usize>().ok())
+                    .sum();
+                let title = format!(
+                    "Monkeytype {} {}",
+                    field(column("mode")),
+                    field(column("mode2"))
+                );
+                TraceRun {
+                    wpm,
+                    accuracy,
+                    total_points: calculate_points(wpm, accuracy),
+                    seconds: number(column("testDuration"), "test duration")?,
+                    date: date(column("timestamp"))?,
+                    title: title.trim().to_string(),
+                    characters,
+                    source: source.to_string(),
+                    language: field(column("language")).to_string(),
+                    difficulty: None,
+                    paragraph: String::new(),
+                    mode: String::new(),
+                    failed: false,
+                    timeline: Timeline::default(),
+                }
+            }
+            ImportFormat::Csv => {
+                let wpm = number(Some(required_column(&mapping.wpm)?), "wpm")?;
+                let accuracy_column = Some(required_column(&mapping.accuracy)?);
+                let mut accuracy = number(accuracy_column, "accuracy")?;
+                if mapping.accuracy_percent || field(accuracy_column).ends_with('%') {
+                    accuracy /= 100.0;
+                }
+                if !(0.0..=1.0).contains(&accuracy) {
+                    return Err(format!(
+                        "Line {}: accuracy \"{}\" is out of range, use \
+                         --map accuracy_scale=percent for percentages",
+                        line,
+                        field(accuracy_column)
+                    ));
+                }
+                let optional_column = |name: &Option<String>| name.as_deref().and_then(column);
+                TraceRun {
+                    wpm,
+                    accuracy,
+                    total_points: calculate_points(wpm, accuracy),
+                    seconds: number(optional_column(&mapping.seconds), "seconds")?,
+                    date: date(optional_column(&mapping.date))?,
+                    title: field(optional_column(&mapping.title)).to_string(),
+                    characters: number(optional_column(&mapping.characters), "characters")?
+                        as usize,
+                    source: source.to_string(),
+                    language: field(optional_column(&mapping.language)).to_string(),
+                    difficulty: None,
+                    paragraph: String::new(),
+                    mode: String::new(),
+                    failed: false,
+                    timeline: Timeline::default(),
+                }
+            }
+        };
+        runs.push(run);
+    }
+    Ok(runs)
+}
+
+/// Parses RFC 3339 dates, `YYYY-MM-DD[ HH:MM[:SS]]` in local time and Unix timestamps, in
+/// seconds or milliseconds.
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    if let Ok(timestamp) = value.parse::<i64>() {
+        // Timestamps in seconds won't reach this until the year 5138.
+        let (seconds, millis) = if timestamp > 100_000_000_000 {
+            (timestamp / 1000, timestamp % 1000)
+        } else {
+            (timestamp, 0)
+        };
+        return DateTime::from_timestamp(seconds, millis as u32 * 1_000_000);
+    }
+    let local = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Local
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Whether two runs are the same run, imported twice.
+fn is_same_run(a: &TraceRun, b: &TraceRun) -> bool {
+    a.source == b.source
+        && a.date == b.date
+        && a.wpm == b.wpm
+        && a.accuracy == b.accuracy
+        && a.seconds == b.seconds
+        && a.title == b.title
+        && a.characters == b.characters
+        && a.language == b.language
+}
+
+/// Appends `imported` to the run history of the profile, leaving the numbers of the runs already
+/// in it as they were. Runs that were already imported are skipped. Returns how many runs were
+/// added.
+pub fn import_runs(profile: &str, imported: Vec<TraceRun>) -> Result<usize, String> {
+    let mut runs = get_track_record(profile);
+    // Only the runs that were there before, identical runs of the same file are all kept.
+    let existing = runs.len();
+    let mut added = 0;
+    for run in imported {
+        if !runs[..existing]
+            .iter()
+            .any(|stored| is_same_run(stored, &run))
+        {
+            runs.push(run);
+            added += 1;
+        }
+    }
+    save_track_record(profile, &runs).map_err(|err| err.to_string())?;
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(content: &str, format: ImportFormat, mapping: &ColumnMapping) -> Vec<TraceRun> {
+        read_runs(content.as_bytes(), format, mapping, format.default_source()).unwrap()
+    }
+
+    #[test]
+    fn reads_monkeytype_results() {
+        let content = "\
+_id,wpm,acc,charStats,mode,mode2,testDuration,timestamp,language
+a1,92.5,96.1,230;4;2;1,time,30,30.01,1700000000000,english
+";
+        let runs = read(content, ImportFormat::Monkeytype, &ColumnMapping::default());
+        assert_eq!(runs.len(), 1);
+        let run = &runs[0];
+        assert_eq!(run.wpm, 92.5);
+        assert!((run.accuracy - 0.961).abs() < 1e-9);
+        assert_eq!(run.seconds, 30.01);
+        // The extra chars aren't in the paragraph.
+        assert_eq!(run.characters, 235);
+        assert_eq!(run.title, "Monkeytype time 30");
+        assert_eq!(run.language, "english");
+        assert_eq!(run.source, "monkeytype");
+        assert_eq!(run.date, DateTime::from_timestamp(1_700_000_000, 0));
+    }
+
+    #[test]
+    fn monkeytype_results_need_wpm_and_accuracy() {
+        let content = "wpm,charStats\n90,1;0;0;0\n";
+        let result = read_runs(
+            content.as_bytes(),
+            ImportFormat::Monkeytype,
+            &ColumnMapping::default(),
+            "monkeytype",
+        );
+        assert_eq!(result.err().unwrap(), "The file has no \"acc\" column");
+    }
+
+    #[test]
+    fn reads_csv_with_the_default_columns() {
+        let content = "\
+wpm,accuracy,seconds,date,title,characters,language
+80,0.95,42.5,2024-01-02T10:00:00Z,Un lugar,300,es
+70,97%,,,,,
+";
+        let runs = read(content, ImportFormat::Csv, &ColumnMapping::default());
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].wpm, 80.0);
+        assert_eq!(runs[0].accuracy, 0.95);
+        assert_eq!(runs[0].seconds, 42.5);
+        assert_eq!(
+            runs[0].date,
+            Some(Utc.with_ymd_and_hms(2024, 1, 2, 10, 0, 0).unwrap())
+        );
+        assert_eq!(runs[0].title, "Un lugar");
+        assert_eq!(runs[0].characters, 300);
+        assert_eq!(runs[0].language, "es");
+        // Percentages are accuracies too, and the optional fields can be empty.
+        assert!((runs[1].accuracy - 0.97).abs() < 1e-9);
+        assert_eq!(runs[1].seconds, 0.0);
+        assert_eq!(runs[1].date, None);
+        assert_eq!(runs[1].source, "csv");
+    }
+
+    #[test]
+    fn reads_csv_with_mapped_columns() {
+        let mapping =
+            ColumnMapping::parse("wpm=Speed, accuracy=Acc,accuracy_scale=percent,date=Day")
+                .unwrap();
+        let content = "Speed,Acc,Day\n65,88,1700000000\n";
+        let runs = read(content, ImportFormat::Csv, &mapping);
+        assert_eq!(runs[0].wpm, 65.0);
+        assert!((runs[0].accuracy - 0.88).abs() < 1e-9);
+        assert_eq!(runs[0].date, DateTime::from_timestamp(1_700_000_000, 0));
+    }
+
+    #[test]
+    fn the_accuracy_scale_is_explicit() {
+        let content = "wpm,accuracy\n60,1\n60,0.5\n";
+        let fractions = read(content, ImportFormat::Csv, &ColumnMapping::default());
+        assert_eq!(fractions[0].accuracy, 1.0);
+        assert_eq!(fractions[1].accuracy, 0.5);
+        let percent = ColumnMapping::parse("accuracy_scale=percent").unwrap();
+        let percentages = read(content, ImportFormat::Csv, &percent);
+        assert_eq!(percentages[0].accuracy, 0.01);
+        assert_eq!(percentages[1].accuracy, 0.005);
+
+        let result = read_runs(
+            "wpm,accuracy\n60,1.5\n".as_bytes(),
+            ImportFormat::Csv,
+            &ColumnMapping::default(),
+            "csv",
+        );
+        assert!(result
+            .err()
+            .unwrap()
+            .starts_with("Line 2: accuracy \"1.5\""));
+        assert!(ColumnMapping::parse("accuracy_scale=ratio").is_err());
+    }
+
+    #[test]
+    fn reports_invalid_values_with_their_line() {
+        let content = "wpm,accuracy\n60,0.9\nfast,0.9\n";
+        let result = read_runs(
+            content.as_bytes(),
+            ImportFormat::Csv,
+            &ColumnMapping::default(),
+            "csv",
+        );
+        assert_eq!(result.err().unwrap(), "Line 3: invalid wpm \"fast\"");
+    }
+
+    #[test]
+    fn rejects_invalid_mappings() {
+        assert!(ColumnMapping::parse("wpm").is_err());
+        assert!(ColumnMapping::parse("speed=Speed").is_err());
+    }
+
+    #[test]
+    fn same_runs_are_compared_by_content() {
+        let content = "wpm,accuracy,title\n60,0.9,A\n60,0.9,B\n";
+        let runs = read(content, ImportFormat::Csv, &ColumnMapping::default());
+        assert!(is_same_run(&runs[0], &runs[0].clone()));
+        assert!(!is_same_run(&runs[0], &runs[1]));
+    }
+}
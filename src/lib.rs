@@ -1,62 +1,391 @@
 use chrono::prelude::*;
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::path::PathBuf;
 use std::{collections::HashMap, rc::Rc};
 use tui::{
     backend::Backend,
-    style::{Color, Style},
+    style::{Modifier, Style},
     text::Span,
     Frame,
 };
 
+pub mod cli;
+pub mod config;
+pub mod daily;
+pub mod database;
+pub mod difficulty;
+pub mod export;
+pub mod import;
+pub mod library;
+pub mod profile;
+pub mod theme;
+pub mod timeline;
 pub mod windows;
 
+use config::{CaretStyle, Config, TypingOptions};
+use daily::Daily;
+use difficulty::{difficulty_score, Difficulty};
+use library::{paragraph_key, Book, LibraryState, OptionsState, RunRules, Selection};
+use profile::profile_path;
+use theme::Theme;
+use timeline::Timeline;
+
 pub struct Padding {
     width: u16,
     height: u16,
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Default)]
 pub struct TraceRun {
     wpm: f64,
     accuracy: f64,
     total_points: f64,
     seconds: f64,
+    // Runs saved before dates were recorded don't have one.
+    #[serde(default)]
+    date: Option<DateTime<Utc>>,
+    /// Title of the paragraph typed, empty for older runs.
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    characters: usize,
+    /// Tool the run was imported from, empty for runs played here.
+    #[serde(default)]
+    source: String,
+    /// Language of the paragraph typed, empty when it isn't known.
+    #[serde(default)]
+    language: String,
+    /// Difficulty score of the paragraph typed, unknown for older and imported runs.
+    #[serde(default)]
+    difficulty: Option<f64>,
+    /// Key of the paragraph typed, see [`library::paragraph_key`]. Empty for older and imported
+    /// runs.
+    #[serde(default)]
+    paragraph: String,
+    /// Rules the run was typed with, see [`library::RunRules::mode`]. Empty for the normal ones.
+    #[serde(default)]
+    mode: String,
+    /// The run ended early by breaking its rules, it's kept out of the statistics.
+    #[serde(default)]
+    failed: bool,
+    /// What was typed in every second, empty for older and imported runs.
+    #[serde(default)]
+    timeline: Timeline,
 }
 
 impl TraceRun {
-    pub fn to_csv(&self) -> String {
-        format!(
-            "{:},{:},{:},{:}",
-            self.wpm, self.accuracy, self.total_points, self.seconds
-        )
-    }
-}
-
-pub fn get_track_record() -> Vec<TraceRun> {
-    let path = get_app_path(".runs.csv");
-    match csv::Reader::from_path(path) {
-        Ok(mut reader) => {
-            let mut records = vec![];
-            for result in reader.deserialize() {
-                if let Ok(record) = result {
-                    records.push(record)
-                }
-            }
-            records
+    /// Mean length of the words counted by the WPM of the run, so the WPM of its seconds can be
+    /// measured the same way. Runs without chars or time use the usual five.
+    pub fn chars_per_word(&self) -> f64 {
+        let words = self.wpm * self.seconds / 60.0;
+        if self.characters > 0 && words > 0.0 {
+            self.characters as f64 / words
+        } else {
+            timeline::CHARS_PER_WORD
         }
+    }
+}
+
+/// Points of a run, accuracy goes from 0 to 1.
+pub fn calculate_points(wpm: f64, accuracy: f64) -> f64 {
+    (wpm + accuracy * wpm) / 2.0
+}
+
+/// Name shown for the source of the runs.
+pub fn source_label(source: &str) -> &str {
+    if source.is_empty() {
+        "TRace"
+    } else {
+        source
+    }
+}
+
+/// Name shown for the language of the runs.
+pub fn language_label(language: &str) -> &str {
+    if language.is_empty() {
+        "Unknown language"
+    } else {
+        language
+    }
+}
+
+/// Runs of the profile, oldest first.
+pub fn get_track_record(profile: &str) -> Vec<TraceRun> {
+    let path = profile_path(profile, ".runs.csv");
+    match csv::ReaderBuilder::new().flexible(true).from_path(path) {
+        Ok(mut reader) => reader.deserialize().flatten().collect(),
         Err(_) => Vec::new(),
     }
 }
 
+/// Appends a run to the `.runs.csv` of the profile. Files written by older versions, with other
+/// columns, are rewritten with the current columns first.
+pub fn save_run(profile: &str, run: &TraceRun) -> Result<(), csv::Error> {
+    let path = profile_path(profile, ".runs.csv");
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+    let header_matches = existing.lines().next() == Some(runs_csv_header().as_str());
+
+    if header_matches && existing.ends_with('\n') {
+        let file = std::fs::OpenOptions::new().append(true).open(&path)?;
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(file);
+        writer.serialize(run)?;
+        writer.flush()?;
+        Ok(())
+    } else {
+        let mut runs = get_track_record(profile);
+        runs.push(run.clone());
+        save_track_record(profile, &runs)
+    }
+}
+
+/// Replaces the whole `.runs.csv` file of the profile with `runs`.
+pub fn save_track_record(profile: &str, runs: &[TraceRun]) -> Result<(), csv::Error> {
+    let path = profile_path(profile, ".runs.csv");
+    // Written to a temporary file first so a crash can't leave a half written history.
+    let tmp_path = profile_path(profile, ".runs.csv.tmp");
+    let mut writer = csv::Writer::from_path(&tmp_path)?;
+    for run in runs {
+        writer.serialize(run)?;
+    }
+    writer.flush()?;
+    std::fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+fn runs_csv_header() -> String {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    let _ = writer.serialize(TraceRun::default());
+    let data = writer.into_inner().unwrap_or_default();
+    String::from_utf8_lossy(&data)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum RunRange {
+    #[default]
+    All,
+    Last10,
+    Last50,
+    Last100,
+    Last7Days,
+    Last30Days,
+}
+
+impl RunRange {
+    pub fn next(self) -> RunRange {
+        match self {
+            RunRange::All => RunRange::Last10,
+            RunRange::Last10 => RunRange::Last50,
+            RunRange::Last50 => RunRange::Last100,
+            RunRange::Last100 => RunRange::Last7Days,
+            RunRange::Last7Days => RunRange::Last30Days,
+            RunRange::Last30Days => RunRange::All,
+        }
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            RunRange::All => "All runs",
+            RunRange::Last10 => "Last 10 runs",
+            RunRange::Last50 => "Last 50 runs",
+            RunRange::Last100 => "Last 100 runs",
+            RunRange::Last7Days => "Last 7 days",
+            RunRange::Last30Days => "Last 30 days",
+        }
+    }
+    /// Indexes of the runs inside the range, oldest first. Only the runs at `indexes` are
+    /// considered.
+    pub fn filter(self, runs: &[TraceRun], indexes: &[usize], now: DateTime<Utc>) -> Vec<usize> {
+        let last = |n: usize| indexes[indexes.len().saturating_sub(n)..].to_vec();
+        let since = |days: i64| {
+            let start = now - chrono::Duration::days(days);
+            indexes
+                .iter()
+                .copied()
+                .filter(|i| runs[*i].date.is_some_and(|date| date >= start))
+                .collect()
+        };
+        match self {
+            RunRange::All => indexes.to_vec(),
+            RunRange::Last10 => last(10),
+            RunRange::Last50 => last(50),
+            RunRange::Last100 => last(100),
+            RunRange::Last7Days => since(7),
+            RunRange::Last30Days => since(30),
+        }
+    }
+}
+
+/// What the statistics window is showing, the runs are read once when the window is opened.
+#[derive(Clone, Default)]
+pub struct StatisticsState {
+    runs: Vec<TraceRun>,
+    range: RunRange,
+    /// Pages are counted from the most recent runs.
+    page: usize,
+    /// Position of the selected run inside the runs of the range.
+    cursor: Option<usize>,
+    /// Shows the summary of the range instead of the charts.
+    dashboard: bool,
+    /// Result of the last export, shown in the export menu.
+    export_message: Option<String>,
+    /// Only the runs of this source are shown, all of them when there's none.
+    source: Option<String>,
+    /// Only the runs in this language are shown, all of them when there's none.
+    language: Option<String>,
+    /// Only the runs of this difficulty are shown, all of them when there's none.
+    difficulty: Option<Difficulty>,
+}
+
+/// Mean WPM, accuracy and points of some runs.
+#[derive(Clone, Copy, Default)]
+pub struct RunAverage {
+    pub wpm: f64,
+    pub accuracy: f64,
+    pub total_points: f64,
+}
+
+impl RunAverage {
+    fn new<'a>(runs: impl ExactSizeIterator<Item = &'a TraceRun>) -> Option<RunAverage> {
+        let count = runs.len() as f64;
+        if count == 0.0 {
+            return None;
+        }
+        let sum = runs.fold(RunAverage::default(), |sum, run| RunAverage {
+            wpm: sum.wpm + run.wpm,
+            accuracy: sum.accuracy + run.accuracy,
+            total_points: sum.total_points + run.total_points,
+        });
+        Some(RunAverage {
+            wpm: sum.wpm / count,
+            accuracy: sum.accuracy / count,
+            total_points: sum.total_points / count,
+        })
+    }
+}
+
+/// Days shown in the runs per day chart of the dashboard.
+pub const RUNS_PER_DAY_DAYS: i64 = 14;
+
+/// Personal bests, averages and totals of some runs, shown in the statistics dashboard.
+pub struct RunSummary {
+    /// Indexes of the runs with the best WPM, accuracy and points.
+    pub best_wpm: Option<usize>,
+    pub best_accuracy: Option<usize>,
+    pub best_points: Option<usize>,
+    pub last_10: Option<RunAverage>,
+    pub last_100: Option<RunAverage>,
+    pub run_count: usize,
+    pub total_seconds: f64,
+    pub total_characters: usize,
+    /// Runs of each of the last days, oldest first, ending today.
+    pub runs_per_day: Vec<(NaiveDate, usize)>,
+    /// Mean runs of the days with at least one run.
+    pub runs_per_active_day: f64,
+    /// Consecutive days with runs, up to today. A streak isn't broken until a whole day passes
+    /// without runs, so it still counts if the last run was yesterday.
+    pub streak: usize,
+    /// Runs that broke their rules, they aren't in the other values.
+    pub failed_runs: usize,
+}
+
+impl RunSummary {
+    /// `indexes` are the runs to summarize, oldest first, and `today` the local date. The failed
+    /// runs among them are only counted.
+    pub fn new(runs: &[TraceRun], indexes: &[usize], today: NaiveDate) -> RunSummary {
+        let (failed, indexes): (Vec<usize>, Vec<usize>) =
+            indexes.iter().partition(|i| runs[**i].failed);
+        let indexes = indexes.as_slice();
+        let best = |value: fn(&TraceRun) -> f64| {
+            indexes
+                .iter()
+                .copied()
+                .max_by(|a, b| value(&runs[*a]).total_cmp(&value(&runs[*b])))
+        };
+        let last = |n: usize| {
+            RunAverage::new(
+                indexes[indexes.len().saturating_sub(n)..]
+                    .iter()
+                    .map(|i| &runs[*i]),
+            )
+        };
+
+        let mut days: HashMap<NaiveDate, usize> = HashMap::new();
+        for i in indexes {
+            if let Some(date) = runs[*i].date {
+                *days
+                    .entry(date.with_timezone(&Local).naive_local().date())
+                    .or_default() += 1;
+            }
+        }
+        let runs_per_day = (0..RUNS_PER_DAY_DAYS)
+            .rev()
+            .map(|days_ago| today - chrono::Duration::days(days_ago))
+            .map(|day| (day, days.get(&day).copied().unwrap_or(0)))
+            .collect();
+        let dated_runs: usize = days.values().sum();
+        let runs_per_active_day = if days.is_empty() {
+            0.0
+        } else {
+            dated_runs as f64 / days.len() as f64
+        };
+        let mut day = if days.contains_key(&today) {
+            today
+        } else {
+            today - chrono::Duration::days(1)
+        };
+        let mut streak = 0;
+        while days.contains_key(&day) {
+            streak += 1;
+            day -= chrono::Duration::days(1);
+        }
+
+        RunSummary {
+            best_wpm: best(|run| run.wpm),
+            best_accuracy: best(|run| run.accuracy),
+            best_points: best(|run| run.total_points),
+            last_10: last(10),
+            last_100: last(100),
+            run_count: indexes.len(),
+            total_seconds: indexes.iter().map(|i| runs[*i].seconds).sum(),
+            total_characters: indexes.iter().map(|i| runs[*i].characters).sum(),
+            runs_per_day,
+            runs_per_active_day,
+            streak,
+            failed_runs: failed.len(),
+        }
+    }
+}
+
+/// What the profile window is showing.
+#[derive(Clone, Default)]
+pub struct ProfilesState {
+    names: Vec<String>,
+    cursor: usize,
+    /// Name being typed, for a new profile or to rename the selected one.
+    input: Option<String>,
+    renaming: bool,
+    /// The delete key has to be pressed twice.
+    confirm_delete: bool,
+    /// Result of the last action, or why the name typed can't be used.
+    message: Option<String>,
+}
+
 #[derive(Deserialize, Clone, Default)]
 pub struct AppParagraph {
     content: String,
     title: String,
     author: String,
     date: String,
+    /// Tag like `es` or `en`, databases without the column use the one of their file name.
+    #[serde(default)]
+    language: String,
 }
 
 impl AppParagraph {
@@ -66,6 +395,7 @@ impl AppParagraph {
             title: "".to_string(),
             author: "".to_string(),
             date: "".to_string(),
+            language: "".to_string(),
         }
     }
     pub fn get_paragraph_chars(&self) -> Vec<ParagraphChar> {
@@ -77,51 +407,97 @@ impl AppParagraph {
     pub fn get_word_count(&self) -> usize {
         self.content.split(' ').count()
     }
+    /// Score from 0 to 100, see [`difficulty_score`].
+    pub fn difficulty_score(&self) -> f64 {
+        difficulty_score(&self.content)
+    }
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::from_score(self.difficulty_score())
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum CharStatus {
     Correct,
     Wrong,
     Default,
     Current,
+    /// Typed past the end of a word, it isn't part of the paragraph.
+    Extra,
 }
 
 #[derive(Clone)]
 pub struct ParagraphChar {
     character: char,
+    typed: Option<char>,
     status: CharStatus,
+    /// It was typed wrong at some point of the run, even if it was fixed later.
+    mistyped: bool,
 }
 
 impl ParagraphChar {
     pub fn new(c: char, status: CharStatus) -> ParagraphChar {
         ParagraphChar {
             character: c,
+            typed: None,
+            status,
+            mistyped: false,
+        }
+    }
+    pub fn new_typed(c: char, typed: char, status: CharStatus) -> ParagraphChar {
+        ParagraphChar {
+            character: c,
+            typed: Some(typed),
             status,
+            mistyped: false,
         }
     }
-    pub fn to_span(&self) -> Span {
-        match self.status {
-            CharStatus::Correct => Span::styled(
-                self.character.to_string(),
-                Style::default().fg(Color::Green),
-            ),
-            CharStatus::Current => Span::styled(
-                self.character.to_string(),
-                Style::default().fg(Color::White).bg(Color::DarkGray),
-            ),
+    /// Spans of the char while it's typed. `blind` shows every typed char alike, right or not.
+    pub fn to_spans(&self, theme: &Theme, options: &TypingOptions, blind: bool) -> Vec<Span<'_>> {
+        let shown = match (&self.status, self.typed) {
+            (CharStatus::Wrong, Some(typed)) if options.show_typed_errors && !blind => typed,
+            _ => self.character,
+        };
+        let style = match self.status {
+            CharStatus::Correct | CharStatus::Wrong | CharStatus::Extra if blind => theme.text,
+            CharStatus::Correct => theme.correct,
+            CharStatus::Current => return caret_spans(self.character, theme, options.caret),
             CharStatus::Wrong => {
-                if self.character == ' ' {
-                    Span::styled(self.character.to_string(), Style::default().bg(Color::Red))
+                if shown == ' ' {
+                    theme.wrong_space
                 } else {
-                    Span::styled(self.character.to_string(), Style::default().fg(Color::Red))
+                    theme.wrong
                 }
             }
-            CharStatus::Default => Span::styled(
-                self.character.to_string(),
-                Style::default().fg(Color::DarkGray),
-            ),
+            CharStatus::Extra => theme.extra,
+            CharStatus::Default => theme.pending,
+        };
+        vec![Span::styled(shown.to_string(), style)]
+    }
+}
+
+fn caret_spans(character: char, theme: &Theme, caret: CaretStyle) -> Vec<Span<'static>> {
+    let caret_color = theme.caret.bg.or(theme.caret.fg);
+    match caret {
+        CaretStyle::Block => vec![Span::styled(character.to_string(), theme.caret)],
+        CaretStyle::Underline => {
+            let mut style = theme
+                .pending
+                .add_modifier(Modifier::UNDERLINED | Modifier::BOLD);
+            style.fg = caret_color.or(style.fg);
+            vec![Span::styled(character.to_string(), style)]
+        }
+        CaretStyle::Bar => {
+            let bar_style = Style {
+                fg: caret_color,
+                ..Style::default()
+            };
+            vec![
+                Span::styled("▏", bar_style),
+                Span::styled(character.to_string(), theme.pending),
+            ]
         }
+        CaretStyle::None => vec![Span::styled(character.to_string(), theme.pending)],
     }
 }
 
@@ -130,7 +506,7 @@ pub fn convert_string_to_chars(s: String) -> Vec<ParagraphChar> {
     for elem in s.chars() {
         vector.push(ParagraphChar::new(elem, CharStatus::Default));
     }
-    return vector;
+    vector
 }
 
 #[derive(Clone)]
@@ -140,6 +516,16 @@ pub struct PlayerStatistics {
     current_error_count: usize,
     total_error_count: usize,
     word_count: usize,
+    /// The run broke its rules before the end of the paragraph.
+    failed: bool,
+    timeline: Timeline,
+}
+
+impl PlayerStatistics {
+    fn add_keystroke(&mut self, correct: bool) {
+        let millis = (Utc::now() - self.initial_time).num_milliseconds();
+        self.timeline.record(millis, correct);
+    }
 }
 
 impl Default for PlayerStatistics {
@@ -150,6 +536,8 @@ impl Default for PlayerStatistics {
             current_error_count: 0,
             total_error_count: 0,
             word_count: 0,
+            failed: false,
+            timeline: Timeline::default(),
         }
     }
 }
@@ -171,54 +559,156 @@ impl Player {
 #[derive(Clone, Default)]
 pub struct State {
     player: Player,
+    #[allow(dead_code)] // Will be used once multiplayer lands
     oponents: Vec<Player>,
     chars: Vec<ParagraphChar>,
     show_bar_charts: bool,
+    statistics: StatisticsState,
+    profiles: ProfilesState,
+    library: LibraryState,
+    /// Which paragraphs are picked for practice, per profile.
+    selection: Selection,
+    options: OptionsState,
+    /// Book the paragraph being typed belongs to, when playing one.
+    book: Option<Book>,
+    /// Challenge of the day, while it's shown or typed.
+    daily: Option<Daily>,
+    /// Rules of the run being typed.
+    rules: RunRules,
     paragraph: AppParagraph,
+    config: Config,
 }
 
 impl State {
+    pub fn with_config(config: Config) -> State {
+        State {
+            config,
+            ..State::default()
+        }
+    }
     pub fn reset(&mut self) {
         self.chars = vec![];
         self.paragraph = AppParagraph::new();
         self.show_bar_charts = false;
     }
+    /// Number of chars of the paragraph, without the extra chars typed by the player.
+    pub fn paragraph_len(&self) -> usize {
+        self.chars
+            .iter()
+            .filter(|c| c.status != CharStatus::Extra)
+            .count()
+    }
+    /// Number of chars of the paragraph already typed.
+    pub fn typed_len(&self) -> usize {
+        self.chars[..self.player.index]
+            .iter()
+            .filter(|c| c.status != CharStatus::Extra)
+            .count()
+    }
     pub fn create_run(&self) -> TraceRun {
-        let accuracy = (self.chars.len() - self.player.statistics.total_error_count) as f64
-            / self.chars.len() as f64;
-        let duration = self.player.statistics.end_time - self.player.statistics.initial_time;
+        let statistics = &self.player.statistics;
+        let paragraph_len = self.paragraph_len();
+        // Failed runs are measured by the part of the paragraph they typed.
+        let characters = if statistics.failed {
+            self.typed_len()
+        } else {
+            paragraph_len
+        };
+        // Runs that failed before typing a char, or that took no time, have no speed or accuracy.
+        let accuracy = if characters == 0 {
+            0.0
+        } else {
+            characters.saturating_sub(statistics.total_error_count) as f64 / characters as f64
+        };
+        let duration = statistics.end_time - statistics.initial_time;
         let seconds = (duration.num_milliseconds() as f64) / 1000.0;
 
-        let wpm = self.player.statistics.word_count as f64 / seconds * 60.0;
-        let total_points = (wpm + accuracy * wpm) / 2.0;
+        let words = if paragraph_len == 0 {
+            0.0
+        } else {
+            statistics.word_count as f64 * characters as f64 / paragraph_len as f64
+        };
+        let wpm = if seconds > 0.0 {
+            words / seconds * 60.0
+        } else {
+            0.0
+        };
+        let total_points = calculate_points(wpm, accuracy);
         TraceRun {
             wpm,
             accuracy,
             total_points,
             seconds,
+            date: Some(statistics.end_time),
+            title: self.paragraph.title.clone(),
+            characters,
+            source: String::new(),
+            language: self.paragraph.language.clone(),
+            difficulty: Some(self.paragraph.difficulty_score()),
+            paragraph: paragraph_key(&self.paragraph),
+            mode: self.rules.mode(),
+            failed: statistics.failed,
+            timeline: statistics.timeline.until(seconds),
         }
     }
 }
 
+pub type WindowAction<B> = Box<dyn Fn(&mut State) -> Option<Window<B>>>;
+pub type WindowRender<B> = Box<dyn Fn(&mut Frame<B>)>;
+pub type WindowUi<B> = Box<dyn Fn(Rc<State>) -> WindowRender<B>>;
+
 pub struct WindowCommand<B: Backend> {
-    pub activator_key: KeyCode,
-    pub action: Box<dyn Fn(&mut State) -> Option<Window<B>>>,
+    pub activator_key: KeyEvent,
+    pub action: WindowAction<B>,
 }
 
 impl<B: Backend> WindowCommand<B> {
-    pub fn new_char_command(
-        activator: char, command: Box<dyn Fn(&mut State) -> Option<Window<B>>>,
-    ) -> WindowCommand<B> {
+    pub fn new_char_command(activator: char, command: WindowAction<B>) -> WindowCommand<B> {
+        WindowCommand::new_key_command(KeyCode::Char(activator), command)
+    }
+    pub fn new_key_command(activator: KeyCode, command: WindowAction<B>) -> WindowCommand<B> {
         WindowCommand {
-            activator_key: KeyCode::Char(activator),
+            activator_key: KeyEvent::from(activator),
             action: command,
         }
     }
 }
 
 pub struct Window<B: Backend> {
-    pub commands: HashMap<KeyCode, WindowCommand<B>>,
-    pub ui: Box<dyn Fn(Rc<State>) -> Box<dyn Fn(&mut Frame<B>)>>,
+    pub commands: HashMap<KeyEvent, WindowCommand<B>>,
+    pub ui: WindowUi<B>,
+}
+
+impl<B: Backend> Window<B> {
+    pub fn get_command(&self, event: KeyEvent) -> Option<&WindowCommand<B>> {
+        self.commands.get(&normalize_key_event(event))
+    }
+}
+
+/// Normalizes a key event so it can be matched against the commands of a window.
+///
+/// For chars the case is already part of the char itself, so `SHIFT` is dropped. Some terminals
+/// report AltGr combinations (used to type chars like `@` on spanish layouts) as `CONTROL | ALT`,
+/// those are treated as plain chars too.
+pub fn normalize_key_event(event: KeyEvent) -> KeyEvent {
+    match event.code {
+        KeyCode::Char(_) => {
+            let mut modifiers = event.modifiers;
+            modifiers.remove(KeyModifiers::SHIFT);
+            if modifiers.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                modifiers.remove(KeyModifiers::CONTROL | KeyModifiers::ALT);
+            }
+            KeyEvent::new(event.code, modifiers)
+        }
+        _ => event,
+    }
+}
+
+/// Keys that close the app from any window.
+pub fn is_quit_event(event: KeyEvent) -> bool {
+    let event = normalize_key_event(event);
+    event.modifiers == KeyModifiers::CONTROL
+        && matches!(event.code, KeyCode::Char('c' | 'C' | 'q' | 'Q'))
 }
 
 pub fn get_app_path(file_path: &str) -> PathBuf {
@@ -250,17 +740,100 @@ pub fn generate_all_chars() -> Vec<char> {
     chars
 }
 
-pub fn add_to_commands<B: 'static + Backend>(
-    commands: &mut HashMap<KeyCode, WindowCommand<B>>, char_array: &Vec<char>,
-    cmd: Box<dyn Fn(char) -> Box<dyn Fn(&mut State) -> Option<Window<B>>>>,
+pub fn add_action_commands<B: 'static + Backend>(
+    commands: &mut HashMap<KeyEvent, WindowCommand<B>>, keys: &[KeyEvent],
+    action: fn(&mut State) -> Option<Window<B>>,
 ) {
-    for elem in char_array {
+    for key in keys {
         commands.insert(
-            KeyCode::Char(*elem),
+            *key,
             WindowCommand {
-                activator_key: KeyCode::Char(*elem),
-                action: cmd(*elem),
+                activator_key: *key,
+                action: Box::new(action),
             },
         );
     }
 }
+
+pub fn add_to_commands<B: 'static + Backend>(
+    commands: &mut HashMap<KeyEvent, WindowCommand<B>>, char_array: &[char],
+    cmd: Box<dyn Fn(char) -> WindowAction<B>>,
+) {
+    for elem in char_array {
+        commands.insert(
+            KeyEvent::from(KeyCode::Char(*elem)),
+            WindowCommand::new_char_command(*elem, cmd(*elem)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_summary_counts_the_failed_runs_apart() {
+        let run = |wpm: f64, failed: bool| TraceRun {
+            wpm,
+            accuracy: 1.0,
+            failed,
+            ..TraceRun::default()
+        };
+        let runs = vec![run(50.0, false), run(120.0, true), run(60.0, false)];
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let summary = RunSummary::new(&runs, &[0, 1, 2], today);
+        assert_eq!(summary.failed_runs, 1);
+        assert_eq!(summary.run_count, 2);
+        assert_eq!(summary.best_wpm, Some(2));
+    }
+
+    #[test]
+    fn chars_per_word_matches_the_wpm_of_the_run() {
+        let run = TraceRun {
+            wpm: 60.0,
+            seconds: 20.0,
+            characters: 120,
+            ..TraceRun::default()
+        };
+        // 20 words of 6 chars in 20 seconds.
+        assert_eq!(run.chars_per_word(), 6.0);
+        assert_eq!(
+            TraceRun::default().chars_per_word(),
+            timeline::CHARS_PER_WORD
+        );
+    }
+
+    #[test]
+    fn runs_that_fail_at_once_have_no_speed() {
+        let paragraph = AppParagraph {
+            content: "Hola mundo".to_string(),
+            ..AppParagraph::new()
+        };
+        let mut state = State {
+            chars: paragraph.get_paragraph_chars(),
+            paragraph,
+            ..State::default()
+        };
+        let statistics = &mut state.player.statistics;
+        statistics.word_count = 2;
+        statistics.total_error_count = 1;
+        statistics.failed = true;
+        statistics.end_time = statistics.initial_time;
+        let run = state.create_run();
+        assert_eq!(run.characters, 0);
+        assert_eq!(run.accuracy, 0.0);
+        assert_eq!(run.wpm, 0.0);
+        assert_eq!(run.total_points, 0.0);
+        assert!(run.failed);
+    }
+
+    #[test]
+    fn runs_without_paragraph_or_time_have_no_speed() {
+        let mut state = State::default();
+        let statistics = &mut state.player.statistics;
+        statistics.end_time = statistics.initial_time + chrono::Duration::seconds(3);
+        let run = state.create_run();
+        assert_eq!(run.wpm, 0.0);
+        assert_eq!(run.accuracy, 0.0);
+    }
+}
@@ -0,0 +1,750 @@
+use crate::difficulty::Difficulty;
+use crate::profile::profile_path;
+use crate::{AppParagraph, TraceRun};
+use rand::prelude::SliceRandom;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Identifies a paragraph across runs of the app, even if rows are added or moved in the
+/// database. It's a FNV-1a hash of the title and content, stable between Rust versions unlike
+/// the hasher of the standard library.
+pub fn paragraph_key(paragraph: &AppParagraph) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let bytes = paragraph
+        .title
+        .bytes()
+        .chain(std::iter::once(b'\n'))
+        .chain(paragraph.content.bytes());
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Reads a TOML file of the profile, a missing or broken file means the default value.
+pub(crate) fn load_profile_toml<T: DeserializeOwned + Default>(profile: &str, file: &str) -> T {
+    std::fs::read_to_string(profile_path(profile, file))
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn save_profile_toml<T: Serialize>(
+    value: &T, profile: &str, file: &str,
+) -> Result<(), String> {
+    let content = toml::to_string(value).map_err(|err| err.to_string())?;
+    std::fs::write(profile_path(profile, file), content).map_err(|err| err.to_string())
+}
+
+/// Paragraphs marked by the player in the library, saved in the `library.toml` of the profile.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct LibraryMarks {
+    pub favourites: BTreeSet<String>,
+    /// Hidden paragraphs are never picked at random.
+    pub hidden: BTreeSet<String>,
+}
+
+impl LibraryMarks {
+    /// Marks of the profile, a missing or broken file means nothing is marked.
+    pub fn load(profile: &str) -> LibraryMarks {
+        load_profile_toml(profile, "library.toml")
+    }
+
+    pub fn save(&self, profile: &str) -> Result<(), String> {
+        save_profile_toml(self, profile, "library.toml")
+    }
+
+    pub fn is_favourite(&self, paragraph: &AppParagraph) -> bool {
+        self.favourites.contains(&paragraph_key(paragraph))
+    }
+
+    pub fn is_hidden(&self, paragraph: &AppParagraph) -> bool {
+        self.hidden.contains(&paragraph_key(paragraph))
+    }
+
+    /// Adds the paragraph to `favourites` or `hidden`, or removes it when it's already there.
+    pub fn toggle(marks: &mut BTreeSet<String>, paragraph: &AppParagraph) {
+        let key = paragraph_key(paragraph);
+        if !marks.remove(&key) {
+            marks.insert(key);
+        }
+    }
+}
+
+/// Which paragraphs are picked for practice, saved in the `selection.toml` of the profile.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct Selection {
+    /// Languages of the paragraphs picked, any language when empty.
+    pub languages: BTreeSet<String>,
+    /// Any difficulty when there's none.
+    pub difficulty: Option<Difficulty>,
+    /// Bounds of the chars of the paragraphs picked, both included.
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    /// Paragraphs typed in the last runs can be picked again.
+    pub allow_repeats: bool,
+    /// Paragraphs never typed are picked first.
+    pub prefer_new: bool,
+    /// Picks the paragraphs in an order that only depends on the seed, so everyone with the same
+    /// databases and options gets the same ones. The history and hidden paragraphs are ignored.
+    pub seed: Option<u64>,
+    /// Paragraphs picked since the seed was set.
+    pub seed_position: usize,
+    /// Errors a run can have, one more fails it. `0` is sudden death.
+    pub max_errors: Option<usize>,
+    /// Wrong chars can't be erased and the run ends with them.
+    pub no_backspace: bool,
+    /// Typed chars don't show whether they're right, the mistakes are shown at the end.
+    pub blind: bool,
+}
+
+/// Rules a run is typed with.
+#[derive(Clone, Copy, Default)]
+pub struct RunRules {
+    pub max_errors: Option<usize>,
+    pub no_backspace: bool,
+    pub blind: bool,
+}
+
+impl RunRules {
+    /// Name of the rules saved with the runs, empty for the normal ones.
+    pub fn mode(self) -> String {
+        let mut parts = vec![];
+        match self.max_errors {
+            Some(0) => parts.push("sudden-death".to_string()),
+            Some(max) => parts.push(format!("max-{}-errors", max)),
+            None => {}
+        }
+        if self.no_backspace {
+            parts.push("no-backspace".to_string());
+        }
+        if self.blind {
+            parts.push("blind".to_string());
+        }
+        parts.join("+")
+    }
+
+    /// Whether a run with `errors` errors has failed.
+    pub fn fails(self, errors: usize) -> bool {
+        self.max_errors.is_some_and(|max| errors > max)
+    }
+}
+
+/// Runs whose paragraphs aren't picked again while there are others to pick.
+const RECENT_RUNS: usize = 20;
+/// Digits of the seeds typed in the options window.
+pub const MAX_SEED_DIGITS: usize = 12;
+
+/// Random numbers that only depend on their seed. Unlike the generators of `rand` they're the
+/// same in every version and platform, so a shared seed gives everyone the same paragraphs.
+/// It's SplitMix64.
+pub struct SeededRandom(u64);
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> SeededRandom {
+        SeededRandom(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Number from 0 to `n`, excluded.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Lengths offered in the options window, other bounds can be written in the file.
+const LENGTH_RANGES: [(Option<usize>, Option<usize>); 4] = [
+    (None, None),
+    (None, Some(200)),
+    (Some(200), Some(400)),
+    (Some(400), None),
+];
+
+/// Error limits offered in the options window, others can be written in the file.
+const ERROR_LIMITS: [Option<usize>; 5] = [None, Some(0), Some(3), Some(5), Some(10)];
+
+/// The option after `current` in `options`, or before it with a negative `offset`. Values that
+/// aren't options go back to the first one.
+fn cycle<T: PartialEq + Clone>(options: &[T], current: &T, offset: isize) -> T {
+    let next = match options.iter().position(|option| option == current) {
+        Some(i) => (i as isize + offset).rem_euclid(options.len() as isize) as usize,
+        None => 0,
+    };
+    options[next].clone()
+}
+
+impl Selection {
+    pub fn load(profile: &str) -> Selection {
+        load_profile_toml(profile, "selection.toml")
+    }
+
+    pub fn save(&self, profile: &str) -> Result<(), String> {
+        save_profile_toml(self, profile, "selection.toml")
+    }
+
+    pub fn allows(&self, paragraph: &AppParagraph) -> bool {
+        let length = paragraph.content.chars().count();
+        (self.languages.is_empty() || self.languages.contains(&paragraph.language))
+            && self
+                .difficulty
+                .is_none_or(|difficulty| paragraph.difficulty() == difficulty)
+            && self.min_length.is_none_or(|min| length >= min)
+            && self.max_length.is_none_or(|max| length <= max)
+    }
+
+    pub fn languages_label(&self) -> String {
+        if self.languages.is_empty() {
+            "All".to_string()
+        } else {
+            self.languages
+                .iter()
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(", ")
+        }
+    }
+
+    pub fn difficulty_label(&self) -> &'static str {
+        self.difficulty.map_or("Any", Difficulty::label)
+    }
+
+    pub fn error_limit_label(&self) -> String {
+        match self.max_errors {
+            None => "Off".to_string(),
+            Some(0) => "Sudden death".to_string(),
+            Some(1) => "1 error".to_string(),
+            Some(max) => format!("{} errors", max),
+        }
+    }
+
+    pub fn cycle_error_limit(&mut self, offset: isize) {
+        self.max_errors = cycle(&ERROR_LIMITS, &self.max_errors, offset);
+    }
+
+    pub fn rules(&self) -> RunRules {
+        RunRules {
+            max_errors: self.max_errors,
+            no_backspace: self.no_backspace,
+            blind: self.blind,
+        }
+    }
+
+    pub fn length_label(&self) -> String {
+        match (self.min_length, self.max_length) {
+            (None, None) => "Any".to_string(),
+            (None, Some(max)) => format!("Up to {} chars", max),
+            (Some(min), None) => format!("{} chars or more", min),
+            (Some(min), Some(max)) => format!("{} to {} chars", min, max),
+        }
+    }
+
+    /// Goes from every language to each of the `available` ones. Sets of languages, written in
+    /// the file, go back to every language.
+    pub fn cycle_language(&mut self, available: &[String], offset: isize) {
+        let options: Vec<BTreeSet<String>> = std::iter::once(BTreeSet::new())
+            .chain(
+                available
+                    .iter()
+                    .map(|language| BTreeSet::from([language.clone()])),
+            )
+            .collect();
+        self.languages = cycle(&options, &self.languages, offset);
+    }
+
+    pub fn cycle_difficulty(&mut self, offset: isize) {
+        let options: Vec<Option<Difficulty>> = std::iter::once(None)
+            .chain(Difficulty::ALL.into_iter().map(Some))
+            .collect();
+        self.difficulty = cycle(&options, &self.difficulty, offset);
+    }
+
+    /// Picks one of `candidates`, the paragraphs allowed by the selection, avoiding the ones
+    /// typed in `runs` as asked. Seeded picks move forward in the sequence of the seed.
+    pub fn pick(
+        &mut self, mut candidates: Vec<AppParagraph>, runs: &[TraceRun],
+    ) -> Option<AppParagraph> {
+        if candidates.is_empty() {
+            return None;
+        }
+        if let Some(seed) = self.seed {
+            // Sorted first so the order of the files doesn't matter.
+            candidates.sort_by_cached_key(paragraph_key);
+            SeededRandom::new(seed).shuffle(&mut candidates);
+            let paragraph = candidates[self.seed_position % candidates.len()].clone();
+            self.seed_position += 1;
+            return Some(paragraph);
+        }
+
+        // How many runs ago each paragraph was typed last.
+        let mut runs_ago: HashMap<&str, usize> = HashMap::new();
+        for (ago, run) in runs.iter().rev().enumerate() {
+            if !run.paragraph.is_empty() {
+                runs_ago.entry(&run.paragraph).or_insert(ago);
+            }
+        }
+        let keys: Vec<String> = candidates.iter().map(paragraph_key).collect();
+        let last_typed = |i: &usize| runs_ago.get(keys[*i].as_str()).copied();
+
+        let mut pool: Vec<usize> = (0..candidates.len()).collect();
+        if self.prefer_new {
+            let new: Vec<usize> = pool
+                .iter()
+                .copied()
+                .filter(|i| last_typed(i).is_none())
+                .collect();
+            if !new.is_empty() {
+                pool = new;
+            }
+        }
+        if !self.allow_repeats {
+            let fresh: Vec<usize> = pool
+                .iter()
+                .copied()
+                .filter(|i| last_typed(i).is_none_or(|ago| ago >= RECENT_RUNS))
+                .collect();
+            pool = if fresh.is_empty() {
+                // Every paragraph was typed lately, the one typed longest ago goes.
+                pool.iter()
+                    .copied()
+                    .max_by_key(last_typed)
+                    .into_iter()
+                    .collect()
+            } else {
+                fresh
+            };
+        }
+        pool.choose(&mut rand::thread_rng())
+            .map(|i| candidates[*i].clone())
+    }
+
+    /// Sets the seed, starting its sequence again.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+        self.seed_position = 0;
+    }
+
+    pub fn cycle_length(&mut self, offset: isize) {
+        (self.min_length, self.max_length) =
+            cycle(&LENGTH_RANGES, &(self.min_length, self.max_length), offset);
+    }
+}
+
+/// Settings of the options window.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PracticeOption {
+    Language,
+    Difficulty,
+    Length,
+    AvoidRepeats,
+    PreferNew,
+    Seed,
+    ErrorLimit,
+    Backspace,
+    Blind,
+}
+
+impl PracticeOption {
+    pub const ALL: [PracticeOption; 9] = [
+        PracticeOption::Language,
+        PracticeOption::Difficulty,
+        PracticeOption::Length,
+        PracticeOption::AvoidRepeats,
+        PracticeOption::PreferNew,
+        PracticeOption::Seed,
+        PracticeOption::ErrorLimit,
+        PracticeOption::Backspace,
+        PracticeOption::Blind,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PracticeOption::Language => "Language",
+            PracticeOption::Difficulty => "Difficulty",
+            PracticeOption::Length => "Length",
+            PracticeOption::AvoidRepeats => "Avoid repeats",
+            PracticeOption::PreferNew => "Prefer new",
+            PracticeOption::Seed => "Seed",
+            PracticeOption::ErrorLimit => "Error limit",
+            PracticeOption::Backspace => "Backspace",
+            PracticeOption::Blind => "Blind",
+        }
+    }
+}
+
+/// What the options window is showing.
+#[derive(Clone, Default)]
+pub struct OptionsState {
+    /// Languages of the databases, read when the window is opened.
+    pub languages: Vec<String>,
+    /// Position of the selected option in [`PracticeOption::ALL`].
+    pub cursor: usize,
+    /// Why the options couldn't be saved.
+    pub message: Option<String>,
+}
+
+/// Where the player is in each book, saved in the `books.toml` of the profile. A book is every
+/// paragraph with the same title, in the order of the database.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct BookProgress {
+    /// Paragraph to type next in each book, counted from 0.
+    pub positions: BTreeMap<String, usize>,
+}
+
+impl BookProgress {
+    pub fn load(profile: &str) -> BookProgress {
+        load_profile_toml(profile, "books.toml")
+    }
+
+    pub fn save(&self, profile: &str) -> Result<(), String> {
+        save_profile_toml(self, profile, "books.toml")
+    }
+
+    /// Paragraph to type next in the book with `title` and `len` paragraphs. Books that lost
+    /// paragraphs since they were saved start again.
+    pub fn position(&self, title: &str, len: usize) -> usize {
+        match self.positions.get(title) {
+            Some(position) if *position < len => *position,
+            _ => 0,
+        }
+    }
+}
+
+/// A book being typed, one paragraph per run.
+#[derive(Clone)]
+pub struct Book {
+    pub title: String,
+    pub paragraphs: Vec<AppParagraph>,
+    /// Paragraph being typed.
+    pub position: usize,
+}
+
+impl Book {
+    /// Opens the book with `title` where the player left it.
+    pub fn open(paragraphs: &[AppParagraph], title: &str, progress: &BookProgress) -> Option<Book> {
+        let paragraphs: Vec<AppParagraph> = paragraphs
+            .iter()
+            .filter(|paragraph| paragraph.title == title)
+            .cloned()
+            .collect();
+        if paragraphs.is_empty() {
+            return None;
+        }
+        Some(Book {
+            title: title.to_string(),
+            position: progress.position(title, paragraphs.len()),
+            paragraphs,
+        })
+    }
+
+    pub fn current(&self) -> &AppParagraph {
+        &self.paragraphs[self.position]
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position + 1 >= self.paragraphs.len()
+    }
+
+    /// Remembers that the current paragraph was typed, a finished book starts again next time.
+    pub fn save_progress(&self, profile: &str) -> Result<(), String> {
+        let mut progress = BookProgress::load(profile);
+        let next = if self.is_finished() {
+            0
+        } else {
+            self.position + 1
+        };
+        progress.positions.insert(self.title.clone(), next);
+        progress.save(profile)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum LibraryFilter {
+    /// Every paragraph but the hidden ones.
+    #[default]
+    Visible,
+    Favourites,
+    Hidden,
+}
+
+impl LibraryFilter {
+    pub fn next(self) -> LibraryFilter {
+        match self {
+            LibraryFilter::Visible => LibraryFilter::Favourites,
+            LibraryFilter::Favourites => LibraryFilter::Hidden,
+            LibraryFilter::Hidden => LibraryFilter::Visible,
+        }
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            LibraryFilter::Visible => "All",
+            LibraryFilter::Favourites => "Favourites",
+            LibraryFilter::Hidden => "Hidden",
+        }
+    }
+}
+
+/// What the library window is showing, the paragraphs are read once when it's opened.
+#[derive(Clone, Default)]
+pub struct LibraryState {
+    pub paragraphs: Vec<AppParagraph>,
+    pub marks: LibraryMarks,
+    pub books: BookProgress,
+    pub filter: LibraryFilter,
+    /// Only paragraphs with the query in their title, author or content are listed.
+    pub query: String,
+    pub searching: bool,
+    /// Position of the selected paragraph inside the listed ones.
+    pub cursor: usize,
+    /// Why the marks couldn't be saved.
+    pub message: Option<String>,
+}
+
+impl LibraryState {
+    /// Indexes of the paragraphs that pass the filter and the query.
+    pub fn listed(&self) -> Vec<usize> {
+        let query = self.query.to_lowercase();
+        (0..self.paragraphs.len())
+            .filter(|i| {
+                let paragraph = &self.paragraphs[*i];
+                let hidden = self.marks.is_hidden(paragraph);
+                match self.filter {
+                    LibraryFilter::Visible => !hidden,
+                    LibraryFilter::Favourites => !hidden && self.marks.is_favourite(paragraph),
+                    LibraryFilter::Hidden => hidden,
+                }
+            })
+            .filter(|i| {
+                let paragraph = &self.paragraphs[*i];
+                query.is_empty()
+                    || [&paragraph.title, &paragraph.author, &paragraph.content]
+                        .iter()
+                        .any(|field| field.to_lowercase().contains(&query))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(content: &str, language: &str) -> AppParagraph {
+        AppParagraph {
+            content: content.to_string(),
+            language: language.to_string(),
+            ..AppParagraph::new()
+        }
+    }
+
+    fn paragraphs(contents: &[&str]) -> Vec<AppParagraph> {
+        contents
+            .iter()
+            .map(|content| paragraph(content, "es"))
+            .collect()
+    }
+
+    /// Runs of the paragraphs, oldest first.
+    fn runs(typed: &[&AppParagraph]) -> Vec<TraceRun> {
+        typed
+            .iter()
+            .map(|paragraph| TraceRun {
+                paragraph: paragraph_key(paragraph),
+                ..TraceRun::default()
+            })
+            .collect()
+    }
+
+    fn contents(picked: &[AppParagraph]) -> Vec<&str> {
+        picked.iter().map(|p| p.content.as_str()).collect()
+    }
+
+    #[test]
+    fn seeded_random_is_splitmix64() {
+        let mut random = SeededRandom::new(0);
+        assert_eq!(random.next_u64(), 0xe220a8397b1dcdaf);
+        assert_eq!(random.next_u64(), 0x6e789e6aa1b965f4);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_sequence() {
+        let sequence = |seed: u64| {
+            let mut random = SeededRandom::new(seed);
+            (0..10).map(|_| random.below(100)).collect::<Vec<usize>>()
+        };
+        assert_eq!(sequence(42), sequence(42));
+        assert_ne!(sequence(42), sequence(43));
+    }
+
+    #[test]
+    fn seeded_picks_follow_the_seed_in_any_file_order() {
+        let contents_1 = ["Uno", "Dos", "Tres", "Cuatro", "Cinco"];
+        let mut reversed = contents_1;
+        reversed.reverse();
+        let pick_all = |candidates: &[&str]| {
+            let mut selection = Selection::default();
+            selection.set_seed(Some(1234));
+            (0..candidates.len())
+                .map(|_| selection.pick(paragraphs(candidates), &[]).unwrap())
+                .collect::<Vec<AppParagraph>>()
+        };
+        let picked = pick_all(&contents_1);
+        assert_eq!(contents(&picked), contents(&pick_all(&reversed)));
+        // Every paragraph comes once before the sequence repeats.
+        let mut sorted = contents(&picked);
+        sorted.sort_unstable();
+        let mut expected = contents_1.to_vec();
+        expected.sort_unstable();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn seeded_picks_ignore_the_history() {
+        let candidates = paragraphs(&["Uno", "Dos", "Tres"]);
+        let mut with_history = Selection::default();
+        with_history.set_seed(Some(7));
+        let mut without_history = with_history.clone();
+        let history = runs(&[&candidates[0], &candidates[1], &candidates[2]]);
+        for _ in 0..3 {
+            assert_eq!(
+                with_history
+                    .pick(candidates.clone(), &history)
+                    .unwrap()
+                    .content,
+                without_history
+                    .pick(candidates.clone(), &[])
+                    .unwrap()
+                    .content
+            );
+        }
+        assert_eq!(with_history.seed_position, 3);
+    }
+
+    #[test]
+    fn language_filter_narrows_the_candidates() {
+        let mut selection = Selection::default();
+        assert!(selection.allows(&paragraph("Hola", "es")));
+        selection.languages = BTreeSet::from(["en".to_string()]);
+        assert!(!selection.allows(&paragraph("Hola", "es")));
+        assert!(selection.allows(&paragraph("Hello", "en")));
+    }
+
+    #[test]
+    fn difficulty_filter_narrows_the_candidates() {
+        let candidate = paragraph("Hola mundo", "es");
+        let mut selection = Selection {
+            difficulty: Some(candidate.difficulty()),
+            ..Selection::default()
+        };
+        assert!(selection.allows(&candidate));
+        for difficulty in Difficulty::ALL {
+            selection.difficulty = Some(difficulty);
+            assert_eq!(
+                selection.allows(&candidate),
+                difficulty == candidate.difficulty()
+            );
+        }
+    }
+
+    #[test]
+    fn length_filter_includes_its_bounds() {
+        let selection = Selection {
+            min_length: Some(3),
+            max_length: Some(5),
+            ..Selection::default()
+        };
+        assert!(!selection.allows(&paragraph("Ab", "es")));
+        assert!(selection.allows(&paragraph("Abc", "es")));
+        assert!(selection.allows(&paragraph("Ábcde", "es")));
+        assert!(!selection.allows(&paragraph("Abcdef", "es")));
+    }
+
+    #[test]
+    fn recent_paragraphs_are_avoided() {
+        let candidates = paragraphs(&["Uno", "Dos", "Tres"]);
+        let history = runs(&[&candidates[0], &candidates[2]]);
+        let mut selection = Selection::default();
+        for _ in 0..20 {
+            let picked = selection.pick(candidates.clone(), &history).unwrap();
+            assert_eq!(picked.content, "Dos");
+        }
+    }
+
+    #[test]
+    fn when_every_paragraph_is_recent_the_oldest_goes() {
+        let candidates = paragraphs(&["Uno", "Dos", "Tres"]);
+        let history = runs(&[
+            &candidates[1],
+            &candidates[0],
+            &candidates[2],
+            &candidates[0],
+        ]);
+        let mut selection = Selection::default();
+        for _ in 0..20 {
+            let picked = selection.pick(candidates.clone(), &history).unwrap();
+            assert_eq!(picked.content, "Dos");
+        }
+    }
+
+    #[test]
+    fn paragraphs_typed_long_ago_are_fresh_again() {
+        let candidates = paragraphs(&["Uno", "Dos"]);
+        let mut typed = vec![&candidates[0]];
+        // "Uno" was typed exactly `RECENT_RUNS` runs ago.
+        typed.extend(std::iter::repeat_n(&candidates[1], RECENT_RUNS));
+        let mut selection = Selection::default();
+        assert_eq!(
+            selection
+                .pick(candidates.clone(), &runs(&typed))
+                .unwrap()
+                .content,
+            "Uno"
+        );
+    }
+
+    #[test]
+    fn prefer_new_picks_unseen_paragraphs_first() {
+        let candidates = paragraphs(&["Uno", "Dos", "Tres"]);
+        let mut typed = vec![&candidates[0], &candidates[2]];
+        // Typed long ago, so they aren't avoided as recent ones.
+        typed.extend(std::iter::repeat_n(&candidates[0], RECENT_RUNS));
+        let history = runs(&typed);
+        let mut selection = Selection {
+            allow_repeats: true,
+            prefer_new: true,
+            ..Selection::default()
+        };
+        for _ in 0..20 {
+            let picked = selection.pick(candidates.clone(), &history).unwrap();
+            assert_eq!(picked.content, "Dos");
+        }
+        // Once every paragraph was typed, any of them can be picked.
+        let history = runs(&[&candidates[0], &candidates[1], &candidates[2]]);
+        assert!(selection.pick(candidates.clone(), &history).is_some());
+    }
+
+    #[test]
+    fn nothing_is_picked_without_candidates() {
+        let mut selection = Selection::default();
+        assert!(selection.pick(vec![], &[]).is_none());
+        selection.set_seed(Some(1));
+        assert!(selection.pick(vec![], &[]).is_none());
+    }
+}
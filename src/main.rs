@@ -4,20 +4,63 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::{io, rc::Rc};
-use trace_game::{windows::create_main_menu_window, State};
-use tui::{backend::CrosstermBackend, Terminal};
+use trace_game::{
+    cli,
+    config::Config,
+    is_quit_event,
+    windows::{create_error_window, create_user_window},
+    State,
+};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal,
+};
 
 fn main() -> Result<(), io::Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(err) = cli::run(&args) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     //Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
 
+    // Restore the terminal even if the app panics, otherwise the user is left on a raw terminal.
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut state = Rc::new(State::default());
-    let mut window = create_main_menu_window(Rc::make_mut(&mut state));
+    let result = run(&mut terminal);
+
+    //Restore terminal
+    restore_terminal()?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn run<B: 'static + Backend>(terminal: &mut Terminal<B>) -> Result<(), io::Error> {
+    let (mut state, mut window) = match Config::load() {
+        Ok(config) => {
+            let mut state = Rc::new(State::with_config(config));
+            let window = create_user_window(Rc::make_mut(&mut state));
+            (state, window)
+        }
+        Err(err) => (
+            Rc::new(State::default()),
+            create_error_window(err, create_user_window),
+        ),
+    };
     loop {
         window = match window {
             None => break,
@@ -25,7 +68,8 @@ fn main() -> Result<(), io::Error> {
                 terminal.draw((current_window.ui)(Rc::clone(&state)))?;
                 let user_input = read()?;
                 match user_input {
-                    Event::Key(event) => match current_window.commands.get(&event.code) {
+                    Event::Key(event) if is_quit_event(event) => break,
+                    Event::Key(event) => match current_window.get_command(event) {
                         None => window,
                         Some(command) => (command.action)(Rc::make_mut(&mut state)),
                     },
@@ -35,13 +79,10 @@ fn main() -> Result<(), io::Error> {
             }
         };
     }
-    //Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
     Ok(())
 }
+
+fn restore_terminal() -> Result<(), io::Error> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)
+}
@@ -0,0 +1,270 @@
+use crate::get_app_path;
+use std::{fs, io, path::PathBuf};
+
+const PROFILES_DIR: &str = "profiles";
+/// Holds the name of the last profile used.
+const CURRENT_PROFILE_FILE: &str = ".user";
+
+fn profile_dir(profile: &str) -> PathBuf {
+    get_app_path(PROFILES_DIR).join(profile)
+}
+
+/// Path of a file of the profile, like its `.runs.csv` or `config.toml`.
+pub fn profile_path(profile: &str, file: &str) -> PathBuf {
+    profile_dir(profile).join(file)
+}
+
+/// Names of every profile, sorted.
+pub fn list_profiles() -> Vec<String> {
+    let mut profiles: Vec<String> = match fs::read_dir(get_app_path(PROFILES_DIR)) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    profiles.sort();
+    profiles
+}
+
+pub fn current_profile() -> Option<String> {
+    let name = fs::read_to_string(get_app_path(CURRENT_PROFILE_FILE)).ok()?;
+    let name = name.trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+pub fn set_current_profile(profile: &str) -> io::Result<()> {
+    fs::write(get_app_path(CURRENT_PROFILE_FILE), profile)
+}
+
+pub const MAX_USERNAME_LENGTH: usize = 20;
+
+/// Usernames are used as directory names and will be sent to other players, so they're limited
+/// to ASCII letters, digits, `_`, `-` and `.`.
+pub fn is_username_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+pub fn invalid_char_message(c: char) -> String {
+    format!(
+        "\"{}\" can't be used, only letters without accents, digits, _, - and .",
+        c
+    )
+}
+
+/// Windows doesn't allow these names for files, even with an extension like `con.txt`.
+const RESERVED_NAMES: [&str; 22] = [
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).to_lowercase();
+    RESERVED_NAMES.contains(&stem.as_str())
+}
+
+pub fn too_long_message() -> String {
+    format!(
+        "The name can't be longer than {} chars",
+        MAX_USERNAME_LENGTH
+    )
+}
+
+/// Checks that `name` can be used for a profile, returns it trimmed. Names are compared ignoring
+/// case, some file systems don't tell them apart.
+pub fn validate_username(name: &str, profiles: &[String]) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("The name can't be empty".to_string());
+    }
+    if name.chars().count() > MAX_USERNAME_LENGTH {
+        return Err(too_long_message());
+    }
+    if let Some(c) = name.chars().find(|c| !is_username_char(*c)) {
+        return Err(invalid_char_message(c));
+    }
+    // Directories starting with a dot are hidden, and `.` and `..` aren't profiles.
+    if name.starts_with('.') {
+        return Err("The name can't start with .".to_string());
+    }
+    // Windows drops the dots at the end of directory names.
+    if name.ends_with('.') {
+        return Err("The name can't end with .".to_string());
+    }
+    if is_reserved_name(name) {
+        return Err(format!("{} is a reserved name", name));
+    }
+    if profiles
+        .iter()
+        .any(|profile| profile.to_lowercase() == name.to_lowercase())
+    {
+        return Err(format!("There's already a profile named {}", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Turns a name saved by older versions, that could have any char, into a valid username.
+fn sanitize_username(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| if is_username_char(c) { c } else { '_' })
+        .take(MAX_USERNAME_LENGTH - 1)
+        .collect();
+    let name = name.trim_matches('.');
+    if is_reserved_name(name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+pub fn create_profile(profile: &str) -> io::Result<()> {
+    let first_profile = list_profiles().is_empty();
+    fs::create_dir_all(profile_dir(profile))?;
+    // Before profiles the history was kept next to the database, it goes to the first one.
+    let legacy_runs = get_app_path(".runs.csv");
+    if first_profile && legacy_runs.exists() {
+        fs::rename(legacy_runs, profile_path(profile, ".runs.csv"))?;
+    }
+    Ok(())
+}
+
+pub fn rename_profile(profile: &str, new_name: &str) -> io::Result<()> {
+    fs::rename(profile_dir(profile), profile_dir(new_name))?;
+    if current_profile().as_deref() == Some(profile) {
+        set_current_profile(new_name)?;
+    }
+    Ok(())
+}
+
+/// Deletes the profile with its run history and settings.
+pub fn delete_profile(profile: &str) -> io::Result<()> {
+    fs::remove_dir_all(profile_dir(profile))?;
+    if current_profile().as_deref() == Some(profile) {
+        fs::remove_file(get_app_path(CURRENT_PROFILE_FILE))?;
+    }
+    Ok(())
+}
+
+/// Older versions had a single user, named in `.user`. It becomes the first profile.
+pub fn migrate_single_user() -> io::Result<()> {
+    if !list_profiles().is_empty() {
+        return Ok(());
+    }
+    let name = match current_profile().map(|name| sanitize_username(&name)) {
+        Some(name) if validate_username(&name, &[]).is_ok() => name,
+        _ => return Ok(()),
+    };
+    create_profile(&name)?;
+    set_current_profile(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// The profiles are found from the current directory, which is shared by the tests.
+    static CURRENT_DIR: Mutex<()> = Mutex::new(());
+
+    fn in_temp_dir(name: &str, test: impl FnOnce()) {
+        let _lock = CURRENT_DIR.lock().unwrap_or_else(|err| err.into_inner());
+        let dir =
+            std::env::temp_dir().join(format!("trace-profile-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let previous = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(test));
+        std::env::set_current_dir(previous).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        if let Err(panic) = result {
+            std::panic::resume_unwind(panic);
+        }
+    }
+
+    #[test]
+    fn validates_usernames() {
+        let profiles = vec!["Ana".to_string()];
+        assert_eq!(
+            validate_username("  bob_1.x-y ", &profiles).unwrap(),
+            "bob_1.x-y"
+        );
+        assert!(validate_username("", &profiles).is_err());
+        assert!(validate_username(&"a".repeat(MAX_USERNAME_LENGTH + 1), &profiles).is_err());
+        assert!(validate_username("ana", &profiles).is_err());
+        assert!(validate_username("José", &profiles).is_err());
+        assert!(validate_username("a/b", &profiles).is_err());
+        assert!(validate_username(".bob", &profiles).is_err());
+        assert!(validate_username("bob.", &profiles).is_err());
+        for name in ["con", "NUL", "com1", "Lpt9", "aux.txt"] {
+            assert_eq!(
+                validate_username(name, &profiles).err().unwrap(),
+                format!("{} is a reserved name", name)
+            );
+        }
+        assert!(validate_username("console", &profiles).is_ok());
+    }
+
+    #[test]
+    fn sanitized_names_are_valid() {
+        for name in ["José Pérez", "..con", "nul", "../etc", &"ñ".repeat(30)] {
+            let sanitized = sanitize_username(name);
+            assert!(
+                validate_username(&sanitized, &[]).is_ok(),
+                "{:?} became {:?}",
+                name,
+                sanitized
+            );
+        }
+        assert_eq!(sanitize_username("José Pérez"), "Jos__P_rez");
+        assert_eq!(sanitize_username("nul"), "nul_");
+    }
+
+    #[test]
+    fn migrates_the_single_user() {
+        in_temp_dir("migrate", || {
+            fs::write(CURRENT_PROFILE_FILE, "José\n").unwrap();
+            fs::write(".runs.csv", "runs").unwrap();
+            migrate_single_user().unwrap();
+            assert_eq!(list_profiles(), vec!["Jos_"]);
+            assert_eq!(current_profile().as_deref(), Some("Jos_"));
+            assert_eq!(
+                fs::read_to_string(profile_path("Jos_", ".runs.csv")).unwrap(),
+                "runs"
+            );
+            // Once there are profiles nothing moves.
+            set_current_profile("other").unwrap();
+            migrate_single_user().unwrap();
+            assert_eq!(list_profiles(), vec!["Jos_"]);
+        });
+    }
+
+    #[test]
+    fn creates_renames_and_deletes_profiles() {
+        in_temp_dir("manage", || {
+            create_profile("ana").unwrap();
+            create_profile("bob").unwrap();
+            set_current_profile("ana").unwrap();
+            assert_eq!(list_profiles(), vec!["ana", "bob"]);
+
+            rename_profile("ana", "carla").unwrap();
+            assert_eq!(list_profiles(), vec!["bob", "carla"]);
+            assert_eq!(current_profile().as_deref(), Some("carla"));
+            rename_profile("bob", "dan").unwrap();
+            assert_eq!(current_profile().as_deref(), Some("carla"));
+
+            delete_profile("dan").unwrap();
+            assert_eq!(current_profile().as_deref(), Some("carla"));
+            delete_profile("carla").unwrap();
+            assert!(list_profiles().is_empty());
+            assert_eq!(current_profile(), None);
+        });
+    }
+}
@@ -0,0 +1,401 @@
+use serde::Deserialize;
+use std::{collections::HashMap, convert::TryFrom};
+use tui::style::{Color, Modifier, Style};
+
+/// Styles used to draw every element of the UI.
+///
+/// Loaded from the `[theme]` table of the config file: `preset` picks one of the built-in themes
+/// and every other key overrides a single element, e.g. `pending = "gray"` or
+/// `caret = "black on yellow"`.
+#[derive(Deserialize, Clone)]
+#[serde(try_from = "HashMap<String, String>")]
+pub struct Theme {
+    pub text: Style,
+    pub correct: Style,
+    pub wrong: Style,
+    pub wrong_space: Style,
+    pub extra: Style,
+    pub pending: Style,
+    pub caret: Style,
+    pub title: Style,
+    pub activator: Style,
+    pub highlight: Style,
+    pub info: Style,
+    pub border: Style,
+    pub error: Style,
+    pub gauge: Style,
+    pub axis: Style,
+    pub chart_label: Style,
+    pub points: Style,
+    pub wpm: Style,
+    pub accuracy: Style,
+}
+
+pub const PRESETS: [&str; 5] = [
+    "default",
+    "light-terminal",
+    "high-contrast",
+    "colourblind-safe",
+    "monochrome",
+];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            text: Style::default(),
+            correct: Style::default().fg(Color::Green),
+            wrong: Style::default().fg(Color::Red),
+            wrong_space: Style::default().bg(Color::Red),
+            extra: Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::ITALIC),
+            pending: Style::default().fg(Color::DarkGray),
+            caret: Style::default().fg(Color::White).bg(Color::DarkGray),
+            title: Style::default().fg(Color::Red),
+            activator: Style::default().fg(Color::Yellow),
+            highlight: Style::default().fg(Color::Yellow),
+            info: Style::default().fg(Color::LightCyan),
+            border: Style::default().fg(Color::DarkGray),
+            error: Style::default().fg(Color::Red),
+            gauge: Style::default().fg(Color::LightCyan).bg(Color::Black),
+            axis: Style::default().fg(Color::LightRed),
+            chart_label: Style::default().fg(Color::White),
+            points: Style::default().fg(Color::LightCyan),
+            wpm: Style::default().fg(Color::LightYellow),
+            accuracy: Style::default().fg(Color::LightGreen),
+        }
+    }
+}
+
+impl Theme {
+    pub fn preset(name: &str) -> Option<Theme> {
+        let fg = |c: Color| Style::default().fg(c);
+        let theme = match normalize_name(name).as_str() {
+            "default" => Theme::default(),
+            "lightterminal" | "light" => Theme {
+                text: fg(Color::Black),
+                correct: fg(Color::Green),
+                wrong: fg(Color::Red),
+                wrong_space: Style::default().bg(Color::Red),
+                extra: fg(Color::Red).add_modifier(Modifier::ITALIC),
+                pending: fg(Color::Indexed(244)),
+                caret: Style::default().fg(Color::White).bg(Color::Blue),
+                title: fg(Color::Red),
+                activator: fg(Color::Magenta),
+                highlight: fg(Color::Blue),
+                info: fg(Color::Blue),
+                border: fg(Color::Indexed(244)),
+                error: fg(Color::Red),
+                gauge: Style::default().fg(Color::Blue).bg(Color::Indexed(254)),
+                axis: fg(Color::Red),
+                chart_label: fg(Color::Black),
+                points: fg(Color::Blue),
+                wpm: fg(Color::Magenta),
+                accuracy: fg(Color::Green),
+            },
+            "highcontrast" => Theme {
+                text: fg(Color::White),
+                correct: fg(Color::LightGreen),
+                wrong: fg(Color::LightRed).add_modifier(Modifier::BOLD),
+                wrong_space: Style::default().bg(Color::LightRed),
+                extra: fg(Color::LightRed).add_modifier(Modifier::ITALIC | Modifier::UNDERLINED),
+                pending: fg(Color::White),
+                caret: Style::default().fg(Color::Black).bg(Color::Yellow),
+                title: fg(Color::White),
+                activator: fg(Color::Yellow),
+                highlight: fg(Color::Yellow),
+                info: fg(Color::LightCyan),
+                border: fg(Color::White),
+                error: fg(Color::LightRed),
+                gauge: Style::default().fg(Color::Yellow).bg(Color::Black),
+                axis: fg(Color::White),
+                chart_label: fg(Color::White),
+                points: fg(Color::LightCyan),
+                wpm: fg(Color::Yellow),
+                accuracy: fg(Color::LightGreen),
+            },
+            // Okabe-Ito palette, distinguishable with the common kinds of colour blindness.
+            "colourblindsafe" | "colorblindsafe" => Theme {
+                text: Style::default(),
+                correct: fg(Color::Indexed(32)),
+                wrong: fg(Color::Indexed(208)),
+                wrong_space: Style::default().bg(Color::Indexed(208)),
+                extra: fg(Color::Indexed(208)).add_modifier(Modifier::ITALIC),
+                pending: fg(Color::Gray),
+                caret: Style::default().fg(Color::Black).bg(Color::Indexed(117)),
+                title: fg(Color::Indexed(208)),
+                activator: fg(Color::Indexed(221)),
+                highlight: fg(Color::Indexed(221)),
+                info: fg(Color::Indexed(117)),
+                border: fg(Color::Gray),
+                error: fg(Color::Indexed(208)),
+                gauge: Style::default().fg(Color::Indexed(117)).bg(Color::Black),
+                axis: fg(Color::Indexed(208)),
+                chart_label: fg(Color::White),
+                points: fg(Color::Indexed(117)),
+                wpm: fg(Color::Indexed(221)),
+                accuracy: fg(Color::Indexed(32)),
+            },
+            "monochrome" => {
+                let plain = Style::default();
+                let reversed = plain.add_modifier(Modifier::REVERSED);
+                Theme {
+                    text: plain,
+                    correct: plain,
+                    wrong: plain.add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+                    wrong_space: reversed,
+                    extra: plain.add_modifier(Modifier::ITALIC | Modifier::CROSSED_OUT),
+                    pending: plain.add_modifier(Modifier::DIM),
+                    caret: reversed,
+                    title: plain,
+                    activator: plain,
+                    highlight: plain.add_modifier(Modifier::BOLD),
+                    info: plain,
+                    border: plain,
+                    error: plain.add_modifier(Modifier::BOLD),
+                    gauge: plain,
+                    axis: plain,
+                    chart_label: plain,
+                    points: plain,
+                    wpm: plain,
+                    accuracy: plain,
+                }
+            }
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    fn element_mut(&mut self, name: &str) -> Option<&mut Style> {
+        let element = match name {
+            "text" => &mut self.text,
+            "correct" => &mut self.correct,
+            "wrong" => &mut self.wrong,
+            "wrong_space" => &mut self.wrong_space,
+            "extra" => &mut self.extra,
+            "pending" => &mut self.pending,
+            "caret" => &mut self.caret,
+            "title" => &mut self.title,
+            "activator" => &mut self.activator,
+            "highlight" => &mut self.highlight,
+            "info" => &mut self.info,
+            "border" => &mut self.border,
+            "error" => &mut self.error,
+            "gauge" => &mut self.gauge,
+            "axis" => &mut self.axis,
+            "chart_label" => &mut self.chart_label,
+            "points" => &mut self.points,
+            "wpm" => &mut self.wpm,
+            "accuracy" => &mut self.accuracy,
+            _ => return None,
+        };
+        Some(element)
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for Theme {
+    type Error = String;
+
+    fn try_from(mut raw: HashMap<String, String>) -> Result<Self, Self::Error> {
+        let mut theme = match raw.remove("preset") {
+            Some(name) => Theme::preset(&name).ok_or_else(|| {
+                format!(
+                    "Unknown theme preset \"{}\", available presets are: {}",
+                    name,
+                    PRESETS.join(", ")
+                )
+            })?,
+            None => Theme::default(),
+        };
+        for (name, value) in raw {
+            let element = theme
+                .element_mut(&name)
+                .ok_or_else(|| format!("Unknown theme element \"{}\"", name))?;
+            *element = parse_style(&value)
+                .ok_or_else(|| format!("Invalid style \"{}\" for \"{}\"", value, name))?;
+        }
+        Ok(theme)
+    }
+}
+
+/// Parses styles like `red`, `bold white on blue`, `#ff8800` or `underlined 208`.
+pub fn parse_style(value: &str) -> Option<Style> {
+    let mut style = Style::default();
+    let mut words = value.split_whitespace();
+    while let Some(word) = words.next() {
+        if word.eq_ignore_ascii_case("on") {
+            style = style.bg(parse_color(words.next()?)?);
+            continue;
+        }
+        style = match normalize_name(word).as_str() {
+            "bold" => style.add_modifier(Modifier::BOLD),
+            "dim" => style.add_modifier(Modifier::DIM),
+            "italic" => style.add_modifier(Modifier::ITALIC),
+            "underlined" | "underline" => style.add_modifier(Modifier::UNDERLINED),
+            "reversed" | "reverse" => style.add_modifier(Modifier::REVERSED),
+            "crossedout" => style.add_modifier(Modifier::CROSSED_OUT),
+            _ => style.fg(parse_color(word)?),
+        };
+    }
+    Some(style)
+}
+
+/// Parses colour names (`light-cyan`, `DarkGray`...), hex values (`#rrggbb`) and 256 colour
+/// palette indexes (`0`-`255`).
+pub fn parse_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        // Checked by chars so multibyte chars can't split the channels.
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if let Ok(index) = value.parse::<u8>() {
+        return Some(Color::Indexed(index));
+    }
+    let color = match normalize_name(value).as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    };
+    Some(color)
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '-' && *c != '_' && *c != ' ')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(entries: &[(&str, &str)]) -> Result<Theme, String> {
+        Theme::try_from(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<HashMap<String, String>>(),
+        )
+    }
+
+    #[test]
+    fn parses_colour_names() {
+        assert_eq!(parse_color("red"), Some(Color::Red));
+        assert_eq!(parse_color("light-cyan"), Some(Color::LightCyan));
+        assert_eq!(parse_color("DarkGray"), Some(Color::DarkGray));
+        assert_eq!(parse_color("dark_grey"), Some(Color::DarkGray));
+        assert_eq!(parse_color("default"), Some(Color::Reset));
+        assert_eq!(parse_color("purple"), None);
+        assert_eq!(parse_color(""), None);
+    }
+
+    #[test]
+    fn parses_hex_colours() {
+        assert_eq!(parse_color("#ff8800"), Some(Color::Rgb(255, 136, 0)));
+        assert_eq!(parse_color("#FF8800"), Some(Color::Rgb(255, 136, 0)));
+        assert_eq!(parse_color("#ff880"), None);
+        assert_eq!(parse_color("#ff88000"), None);
+        assert_eq!(parse_color("#gg8800"), None);
+        assert_eq!(parse_color("#+f+f+f"), None);
+        assert_eq!(parse_color("#"), None);
+    }
+
+    #[test]
+    fn rejects_hex_colours_with_multibyte_chars() {
+        // Six bytes, but the channels would split the "é".
+        assert_eq!(parse_color("#aé1234"), None);
+        assert_eq!(parse_color("#ééé"), None);
+    }
+
+    #[test]
+    fn parses_palette_indexes() {
+        assert_eq!(parse_color("0"), Some(Color::Indexed(0)));
+        assert_eq!(parse_color("255"), Some(Color::Indexed(255)));
+        assert_eq!(parse_color("256"), None);
+        assert_eq!(parse_color("-1"), None);
+    }
+
+    #[test]
+    fn parses_styles() {
+        assert_eq!(parse_style("red"), Some(Style::default().fg(Color::Red)));
+        assert_eq!(
+            parse_style("bold white on blue"),
+            Some(
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Blue)
+                    .add_modifier(Modifier::BOLD)
+            )
+        );
+        assert_eq!(
+            parse_style("underlined ON #000000 crossed-out 208"),
+            Some(
+                Style::default()
+                    .fg(Color::Indexed(208))
+                    .bg(Color::Rgb(0, 0, 0))
+                    .add_modifier(Modifier::UNDERLINED | Modifier::CROSSED_OUT)
+            )
+        );
+        assert_eq!(parse_style(""), Some(Style::default()));
+    }
+
+    #[test]
+    fn rejects_malformed_styles() {
+        assert_eq!(parse_style("blinking red"), None);
+        assert_eq!(parse_style("red on"), None);
+        assert_eq!(parse_style("red on purple"), None);
+        assert_eq!(parse_style("#aé1234"), None);
+    }
+
+    #[test]
+    fn loads_every_preset() {
+        for name in PRESETS {
+            assert!(Theme::preset(name).is_some(), "{}", name);
+            assert!(theme(&[("preset", name)]).is_ok(), "{}", name);
+        }
+        assert!(Theme::preset("solarized").is_none());
+    }
+
+    #[test]
+    fn elements_override_the_preset() {
+        let loaded = theme(&[("preset", "monochrome"), ("caret", "black on yellow")]).unwrap();
+        assert_eq!(
+            loaded.caret,
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        );
+        assert_eq!(loaded.text, Theme::preset("monochrome").unwrap().text);
+    }
+
+    #[test]
+    fn rejects_malformed_themes() {
+        let error = |entries: &[(&str, &str)]| theme(entries).err().unwrap();
+        assert!(error(&[("preset", "solarized")]).starts_with("Unknown theme preset \"solarized\""));
+        assert_eq!(
+            error(&[("background", "red")]),
+            "Unknown theme element \"background\""
+        );
+        assert_eq!(
+            error(&[("caret", "#aé1234")]),
+            "Invalid style \"#aé1234\" for \"caret\""
+        );
+    }
+}
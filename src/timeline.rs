@@ -0,0 +1,239 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Errors in a second that make it an error burst.
+const ERROR_BURST: usize = 2;
+/// Chars in a word for runs that don't say how long their words are, the usual measure of WPM.
+pub const CHARS_PER_WORD: f64 = 5.0;
+/// Seconds typing slower than this share of the mean speed of the run are hesitations.
+const HESITATION_SHARE: f64 = 1.0 / 3.0;
+
+/// What was typed in a second of a run.
+#[derive(Clone, Copy, Default)]
+pub struct TimelineSecond {
+    /// Chars typed right.
+    pub chars: usize,
+    pub errors: usize,
+}
+
+/// What was typed in every second of a run, sampled while it's typed. It's saved in the runs
+/// file as the chars and errors of each second, like `5:0 7:1 6:0`.
+#[derive(Clone, Default)]
+pub struct Timeline(pub Vec<TimelineSecond>);
+
+impl Timeline {
+    /// Counts a key pressed `millis` after the start of the run.
+    pub fn record(&mut self, millis: i64, correct: bool) {
+        let second = (millis / 1000).max(0) as usize;
+        if self.0.len() <= second {
+            self.0.resize(second + 1, TimelineSecond::default());
+        }
+        if correct {
+            self.0[second].chars += 1;
+        } else {
+            self.0[second].errors += 1;
+        }
+    }
+
+    /// The timeline of a run that lasted `seconds`, with the last seconds without keys too.
+    pub fn until(&self, seconds: f64) -> Timeline {
+        let mut timeline = self.clone();
+        let len = (seconds.ceil() as usize).max(timeline.0.len());
+        timeline.0.resize(len, TimelineSecond::default());
+        timeline
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn points(&self, value: impl Fn(&TimelineSecond) -> f64) -> Vec<(f64, f64)> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(i, second)| ((i + 1) as f64, value(second)))
+            .collect()
+    }
+
+    /// WPM of every second, counting the chars typed right in it. `chars_per_word` is the mean
+    /// length of the words of the run, so the seconds match the WPM of the whole run.
+    pub fn wpm(&self, chars_per_word: f64) -> Vec<(f64, f64)> {
+        self.points(|second| second.chars as f64 * 60.0 / chars_per_word)
+    }
+
+    /// The seconds until the last key was pressed, without the idle ones added by `until`.
+    fn typed(&self) -> &[TimelineSecond] {
+        let len = self
+            .0
+            .iter()
+            .rposition(|second| second.chars + second.errors > 0)
+            .map_or(0, |last| last + 1);
+        &self.0[..len]
+    }
+
+    /// WPM of the seconds typed much slower than the rest of the run.
+    pub fn hesitations(&self, chars_per_word: f64) -> Vec<(f64, f64)> {
+        let typed = self.typed();
+        let mean = typed.iter().map(|second| second.chars).sum::<usize>() as f64
+            / typed.len().max(1) as f64;
+        self.wpm(chars_per_word)
+            .into_iter()
+            .zip(typed)
+            .filter(|(_, second)| (second.chars as f64) < mean * HESITATION_SHARE)
+            .map(|(point, _)| point)
+            .collect()
+    }
+
+    /// WPM of the seconds with an error burst.
+    pub fn error_bursts(&self, chars_per_word: f64) -> Vec<(f64, f64)> {
+        self.wpm(chars_per_word)
+            .into_iter()
+            .zip(&self.0)
+            .filter(|(_, second)| second.errors >= ERROR_BURST)
+            .map(|(point, _)| point)
+            .collect()
+    }
+}
+
+impl fmt::Display for Timeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds: Vec<String> = self
+            .0
+            .iter()
+            .map(|second| format!("{}:{}", second.chars, second.errors))
+            .collect();
+        write!(f, "{}", seconds.join(" "))
+    }
+}
+
+impl FromStr for Timeline {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Timeline, String> {
+        value
+            .split_whitespace()
+            .map(|second| {
+                let (chars, errors) = second
+                    .split_once(':')
+                    .ok_or_else(|| format!("\"{}\" isn't chars:errors", second))?;
+                Ok(TimelineSecond {
+                    chars: chars
+                        .parse()
+                        .map_err(|_| format!("\"{}\" isn't a number", chars))?,
+                    errors: errors
+                        .parse()
+                        .map_err(|_| format!("\"{}\" isn't a number", errors))?,
+                })
+            })
+            .collect::<Result<Vec<TimelineSecond>, String>>()
+            .map(Timeline)
+    }
+}
+
+impl Serialize for Timeline {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timeline {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Timeline, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline(seconds: &[(usize, usize)]) -> Timeline {
+        Timeline(
+            seconds
+                .iter()
+                .map(|&(chars, errors)| TimelineSecond { chars, errors })
+                .collect(),
+        )
+    }
+
+    fn seconds(timeline: &Timeline) -> Vec<(usize, usize)> {
+        timeline.0.iter().map(|s| (s.chars, s.errors)).collect()
+    }
+
+    #[test]
+    fn record_counts_keys_in_their_second() {
+        let mut timeline = Timeline::default();
+        timeline.record(0, true);
+        timeline.record(999, false);
+        timeline.record(2500, true);
+        timeline.record(-10, true);
+        assert_eq!(seconds(&timeline), vec![(2, 1), (0, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn until_pads_the_idle_seconds() {
+        let timeline = timeline(&[(3, 0)]).until(2.1);
+        assert_eq!(seconds(&timeline), vec![(3, 0), (0, 0), (0, 0)]);
+        // Never cuts the seconds that were typed.
+        let timeline = timeline.until(1.0);
+        assert_eq!(timeline.0.len(), 3);
+    }
+
+    #[test]
+    fn wpm_counts_the_right_chars_of_each_second() {
+        let timeline = timeline(&[(5, 3), (10, 0)]);
+        assert_eq!(
+            timeline.wpm(CHARS_PER_WORD),
+            vec![(1.0, 60.0), (2.0, 120.0)]
+        );
+        assert_eq!(timeline.wpm(10.0), vec![(1.0, 30.0), (2.0, 60.0)]);
+    }
+
+    #[test]
+    fn hesitations_are_the_slow_seconds() {
+        let timeline = timeline(&[(6, 0), (6, 0), (1, 0), (6, 0)]);
+        assert_eq!(timeline.hesitations(CHARS_PER_WORD), vec![(3.0, 12.0)]);
+    }
+
+    #[test]
+    fn hesitations_ignore_the_idle_seconds_after_the_run() {
+        let timeline = timeline(&[(6, 0), (6, 0), (6, 0)]).until(10.0);
+        assert!(timeline.hesitations(CHARS_PER_WORD).is_empty());
+    }
+
+    #[test]
+    fn error_bursts_have_several_errors() {
+        let timeline = timeline(&[(4, 1), (2, 2), (0, 5)]);
+        assert_eq!(
+            timeline.error_bursts(CHARS_PER_WORD),
+            vec![(2.0, 24.0), (3.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn round_trips_through_strings() {
+        let timeline = timeline(&[(5, 0), (7, 1), (0, 0)]);
+        let text = timeline.to_string();
+        assert_eq!(text, "5:0 7:1 0:0");
+        assert_eq!(seconds(&text.parse().unwrap()), seconds(&timeline));
+        assert!("".parse::<Timeline>().unwrap().is_empty());
+        assert!("5".parse::<Timeline>().is_err());
+        assert!("5:x".parse::<Timeline>().is_err());
+    }
+
+    #[test]
+    fn round_trips_through_a_csv_field() {
+        let timeline = timeline(&[(5, 0), (7, 1)]);
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize((1, &timeline)).unwrap();
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(csv, "1,5:0 7:1\n");
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(csv.as_bytes());
+        let (_, parsed): (usize, Timeline) = reader.deserialize().next().unwrap().unwrap();
+        assert_eq!(seconds(&parsed), seconds(&timeline));
+    }
+}
@@ -1,16 +1,19 @@
+use crate::theme::Theme;
 use crate::Padding;
+use crate::TraceRun;
 use tui::backend::Backend;
 use tui::layout::Constraint;
 use tui::layout::Direction;
 use tui::layout::Layout;
 use tui::layout::Rect;
+use tui::symbols;
 use tui::text::Text;
 use tui::Frame;
 use tui::{
     layout::Alignment,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Wrap},
 };
 
 pub mod main_menu_window;
@@ -40,38 +43,132 @@ pub use join_server_window::*;
 pub mod error_window;
 pub use error_window::*;
 
+pub mod library_window;
+pub use library_window::*;
+
+pub mod options_window;
+pub use options_window::*;
+
+pub mod daily_window;
+pub use daily_window::*;
+
+const BUTTON_HEIGHT: u16 = 3;
+/// Height of the borderless buttons used when the terminal can't fit the bordered ones.
+const COMPACT_BUTTON_HEIGHT: u16 = 1;
+
+/// Returns a rect of `width` x `height` centered inside `area`, shrunk if it doesn't fit.
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Draws a warning instead of the window when the terminal is smaller than the minimum size the
+/// window needs. Returns `true` when the window shouldn't be drawn.
+pub fn render_too_small<B: Backend>(
+    f: &mut Frame<B>, min_width: u16, min_height: u16, theme: &Theme,
+) -> bool {
+    let size = f.size();
+    if size.width >= min_width && size.height >= min_height {
+        return false;
+    }
+    let message = Paragraph::new(vec![
+        Spans::from(Span::styled(
+            "Terminal too small",
+            theme.error.add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(Span::styled(
+            format!(
+                "{}x{}, needs {}x{}",
+                size.width, size.height, min_width, min_height
+            ),
+            theme.text,
+        )),
+    ])
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true });
+    f.render_widget(message, centered_rect(size, size.width, 2));
+    true
+}
+
+/// Minimum width and height needed to draw a menu with `create_menu`.
+pub fn menu_size(title: &str, buttons: &[(&str, &str)]) -> (u16, u16) {
+    menu_size_with(title, buttons, BUTTON_HEIGHT)
+}
+
+fn menu_size_with(title: &str, buttons: &[(&str, &str)], button_height: u16) -> (u16, u16) {
+    let title_width = title
+        .lines()
+        .map(|line| Spans::from(line).width())
+        .max()
+        .unwrap_or(0);
+    let button_width = buttons
+        .iter()
+        .map(|(activator, rest)| Spans::from(vec![Span::raw(*activator), Span::raw(*rest)]).width())
+        .max()
+        .unwrap_or(0)
+        + 4;
+    let width = std::cmp::max(title_width, button_width) as u16 + 4;
+    let height = menu_content_height(title, buttons.len(), button_height) + 4;
+    (width, height)
+}
+
+fn menu_content_height(title: &str, button_count: usize, button_height: u16) -> u16 {
+    title.lines().count() as u16 + 1 + button_height * button_count as u16
+}
+
+/// Draws a centered menu sized after its content, using the extra space of big terminals as
+/// padding around the buttons. Terminals too short for the bordered buttons get a compact list of
+/// one row buttons instead.
 pub fn create_menu<'a, B: Backend>(
-    f: &mut Frame<B>, container: Rect, title: &str, buttons: Vec<(&'a str, &'a str)>,
+    f: &mut Frame<B>, title: &str, buttons: Vec<(&'a str, &'a str)>, theme: &Theme,
 ) {
-    create_menu_with_pad(
-        f,
-        container,
-        title,
-        buttons,
-        Padding {
-            width: container.width / 3,
-            height: container.height / 10,
-        },
+    let size = f.size();
+    let (_, full_height) = menu_size(title, &buttons);
+    let button_height = if size.height >= full_height {
+        BUTTON_HEIGHT
+    } else {
+        COMPACT_BUTTON_HEIGHT
+    };
+    let (min_width, min_height) = menu_size_with(title, &buttons, button_height);
+    if render_too_small(f, min_width, min_height, theme) {
+        return;
+    }
+    let container = centered_rect(
+        size,
+        (size.width / 2).clamp(min_width, std::cmp::max(min_width, 70)),
+        min_height + (size.height - min_height) / 4,
     );
+    let content_height = menu_content_height(title, buttons.len(), button_height);
+    let padding = Padding {
+        width: std::cmp::min(container.width / 6, (container.width - min_width) / 2 + 1),
+        height: (container.height - content_height) / 2,
+    };
+    create_menu_with_pad(f, container, title, buttons, button_height, padding, theme);
 }
 
 pub fn create_menu_with_pad<'a, B: Backend>(
     f: &mut Frame<B>, container: Rect, title: &str, buttons: Vec<(&'a str, &'a str)>,
-    padding: Padding,
+    button_height: u16, padding: Padding, theme: &Theme,
 ) {
     let main_block = Block::default().borders(Borders::ALL);
     f.render_widget(main_block, container);
 
-    let mut constraints = vec![Constraint::Percentage(20)];
+    let mut constraints = vec![Constraint::Length(title.lines().count() as u16 + 1)];
     let mut buttons_constraints: Vec<Constraint> = (0..buttons.len())
-        .map(|_| Constraint::Percentage((80.0 / buttons.len() as f64) as u16))
+        .map(|_| Constraint::Length(button_height))
         .collect();
     constraints.append(&mut buttons_constraints);
-    constraints.push(Constraint::Percentage(1)); // Padding just so the last element doesn't get stretched
+    constraints.push(Constraint::Min(0)); // Padding just so the last element doesn't get stretched
 
     let title_par = Paragraph::new(Text::styled(
         title,
-        Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+        theme.title.add_modifier(Modifier::BOLD),
     ))
     .alignment(Alignment::Center);
 
@@ -84,20 +181,27 @@ pub fn create_menu_with_pad<'a, B: Backend>(
 
     f.render_widget(title_par, chunks[0]);
     for i in 0..buttons.len() {
-        create_centered_button(buttons[i].0, buttons[i].1, chunks[i+1], f);
+        if button_height < BUTTON_HEIGHT {
+            let text = create_ui_button(buttons[i].0, buttons[i].1, theme);
+            f.render_widget(text, chunks[i + 1]);
+        } else {
+            create_centered_button(buttons[i].0, buttons[i].1, chunks[i + 1], f, theme);
+        }
     }
 }
 
-pub fn create_label_widget<'a>(label: &'a str, value: &'a str, color: Color) -> Paragraph<'a> {
+pub fn create_label_widget<'a>(label: &'a str, value: &'a str, style: Style) -> Paragraph<'a> {
     Paragraph::new(vec![Spans::from(vec![
         Span::from(label),
-        Span::styled(value, Style::default().fg(color)),
+        Span::styled(value, style),
     ])])
     .alignment(Alignment::Center)
 }
 
-pub fn create_centered_button<'a, B: Backend>(activator: &'a str, rest: &'a str, container: Rect, f: &mut Frame<B>) {
-    let text = create_ui_button(activator, rest);
+pub fn create_centered_button<'a, B: Backend>(
+    activator: &'a str, rest: &'a str, container: Rect, f: &mut Frame<B>, theme: &Theme,
+) {
+    let text = create_ui_button(activator, rest, theme);
     let borders = Block::default().borders(Borders::ALL);
     let layout = Layout::default()
         .direction(Direction::Vertical)
@@ -108,16 +212,95 @@ pub fn create_centered_button<'a, B: Backend>(activator: &'a str, rest: &'a str,
     f.render_widget(text, layout[0]);
 }
 
-pub fn create_ui_button<'a>(activator: &'a str, rest: &'a str) -> Paragraph<'a> {
+pub fn create_ui_button<'a>(activator: &'a str, rest: &'a str, theme: &Theme) -> Paragraph<'a> {
     let button_text = vec![Spans::from(vec![
         Span::styled(
             activator,
-            Style::default()
-                .add_modifier(Modifier::UNDERLINED | Modifier::BOLD)
-                .fg(Color::Yellow),
+            theme
+                .activator
+                .add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
         ),
-        Span::raw(rest),
+        Span::styled(rest, theme.text),
     ])];
 
     Paragraph::new(button_text).alignment(Alignment::Center)
 }
+
+pub fn line_dataset<'a>(name: &'a str, data: &'a [(f64, f64)], style: Style) -> Dataset<'a> {
+    Dataset::default()
+        .name(name)
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(style)
+        .data(data)
+}
+
+/// Bounds that fit every value, rounded to tens so the labels are readable.
+pub fn value_bounds(values: &[&[(f64, f64)]], min_bound: f64, max_bound: f64) -> [f64; 2] {
+    let all_values = || values.iter().flat_map(|series| series.iter().map(|p| p.1));
+    let max = all_values().fold(f64::NAN, f64::max);
+    let min = all_values().fold(f64::NAN, f64::min);
+    if max.is_nan() {
+        return [min_bound, max_bound];
+    }
+    let upper = ((max * 1.05) / 10.0).ceil() * 10.0;
+    let lower = ((min * 0.95) / 10.0).floor() * 10.0;
+    let upper = upper.clamp(min_bound + 10.0, max_bound);
+    let lower = lower.clamp(min_bound, upper - 10.0);
+    [lower, upper]
+}
+
+pub fn axis_labels(bounds: [f64; 2], count: usize) -> Vec<Span<'static>> {
+    (0..count)
+        .map(|i| {
+            let value = bounds[0] + (bounds[1] - bounds[0]) * i as f64 / (count - 1) as f64;
+            Span::from(format!("{:.0}", value))
+        })
+        .collect()
+}
+
+/// Chart of the WPM of every second of a run, marking the hesitations and error bursts.
+pub fn render_timeline<B: Backend>(
+    f: &mut Frame<B>, area: Rect, run: &TraceRun, title: &str, theme: &Theme,
+) {
+    let chars_per_word = run.chars_per_word();
+    let wpm = run.timeline.wpm(chars_per_word);
+    let hesitations = run.timeline.hesitations(chars_per_word);
+    let error_bursts = run.timeline.error_bursts(chars_per_word);
+    let x_bounds = [1.0, (wpm.len() as f64).max(2.0)];
+    let y_bounds = value_bounds(&[&wpm], 0.0, f64::INFINITY);
+    let chart = Chart::new(vec![
+        line_dataset("WPM", &wpm, theme.wpm),
+        Dataset::default()
+            .name("Hesitation")
+            .marker(symbols::Marker::Dot)
+            .graph_type(GraphType::Scatter)
+            .style(theme.info)
+            .data(&hesitations),
+        Dataset::default()
+            .name("Error burst")
+            .marker(symbols::Marker::Dot)
+            .graph_type(GraphType::Scatter)
+            .style(theme.wrong)
+            .data(&error_bursts),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border)
+            .title(title),
+    )
+    .x_axis(
+        Axis::default()
+            .style(theme.axis)
+            .bounds(x_bounds)
+            .labels(axis_labels(x_bounds, 3)),
+    )
+    .y_axis(
+        Axis::default()
+            .style(theme.axis)
+            .bounds(y_bounds)
+            .labels(axis_labels(y_bounds, 3)),
+    );
+    f.render_widget(chart, area);
+}
@@ -1,26 +1,27 @@
 use crate::windows::*;
 use crate::State;
 use crate::Window;
-use crate::WindowCommand;
-use crossterm::event::KeyCode;
+use crate::WindowRender;
+use crate::{add_action_commands, config::Action};
 use std::collections::HashMap;
 use std::rc::Rc;
 use tui::backend::Backend;
-use tui::Frame;
 
-fn mp_create_server_window<B: 'static + Backend>(_: Rc<State>) -> Box<dyn Fn(&mut Frame<B>)> {
-    Box::new(|f| {})
+fn mp_create_server_window<B: 'static + Backend>(_: Rc<State>) -> WindowRender<B> {
+    Box::new(|_| {})
 }
 
-pub fn create_mp_create_server_window<B: 'static + Backend>(_: &mut State) -> Option<Window<B>> {
+pub fn create_mp_create_server_window<B: 'static + Backend>(
+    state: &mut State,
+) -> Option<Window<B>> {
+    let mut commands = HashMap::new();
+    add_action_commands(
+        &mut commands,
+        state.config.key_bindings.keys(Action::Back),
+        create_multiplayer_menu_window,
+    );
     Some(Window {
         ui: Box::new(mp_create_server_window),
-        commands: HashMap::from([(
-            KeyCode::Esc,
-            WindowCommand {
-                activator_key: KeyCode::Esc,
-                action: Box::new(create_multiplayer_menu_window),
-            },
-        )]),
+        commands,
     })
 }
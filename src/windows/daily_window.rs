@@ -0,0 +1,150 @@
+use crate::config::{key_to_string, Action};
+use crate::daily::{daily_paragraph, Daily};
+use crate::database::load_paragraphs;
+use crate::windows::*;
+use crate::State;
+use crate::Window;
+use crate::WindowCommand;
+use crate::WindowRender;
+use crate::{add_action_commands, Local};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::HashMap;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::layout::Alignment;
+use tui::style::Modifier;
+use tui::text::{Span, Spans};
+use tui::widgets::Paragraph;
+
+const MIN_WIDTH: u16 = 50;
+/// Profiles shown in the leaderboard.
+const LEADERBOARD_ROWS: usize = 10;
+
+fn daily_window<B: 'static + Backend>(state: Rc<State>) -> WindowRender<B> {
+    Box::new(move |f| {
+        let theme = &state.config.theme;
+        let daily = match &state.daily {
+            Some(daily) => daily,
+            None => return,
+        };
+        let key = |action: Action| {
+            state
+                .config
+                .key_bindings
+                .keys(action)
+                .first()
+                .map(|key| key_to_string(*key))
+                .unwrap_or_default()
+        };
+
+        let mut lines = vec![
+            Spans::from(Span::styled(
+                format!("Daily challenge · {}", daily.date.format("%Y-%m-%d")),
+                theme.title.add_modifier(Modifier::BOLD),
+            )),
+            Spans::from(Span::styled(
+                format!("{} · {}", daily.paragraph.title, daily.paragraph.author),
+                theme.info,
+            )),
+            Spans::from(""),
+            Spans::from(Span::styled(
+                match daily.streak {
+                    1 => "Streak: 1 day".to_string(),
+                    streak => format!("Streak: {} days", streak),
+                },
+                theme.highlight,
+            )),
+            Spans::from(""),
+        ];
+        if daily.leaderboard.is_empty() {
+            lines.push(Spans::from(Span::styled(
+                "Nobody finished today's challenge yet",
+                theme.text,
+            )));
+        }
+        for (i, (name, score)) in daily.leaderboard.iter().take(LEADERBOARD_ROWS).enumerate() {
+            let text = format!(
+                "{:>2}. {:<20} {:>7.2} pts {:>7.2} wpm {:>6.2} %",
+                i + 1,
+                name,
+                score.points,
+                score.wpm,
+                score.accuracy * 100.0
+            );
+            lines.push(Spans::from(if *name == state.player.user_name {
+                Span::styled(text, theme.highlight.add_modifier(Modifier::BOLD))
+            } else {
+                Span::styled(text, theme.text)
+            }));
+        }
+        lines.push(Spans::from(""));
+        let status = if !daily.attempted {
+            "Only your first attempt of the day is scored".to_string()
+        } else {
+            match daily.rank(&state.player.user_name) {
+                Some(rank) => format!("You're #{} today, come back tomorrow", rank),
+                None => "You left today's challenge unfinished, come back tomorrow".to_string(),
+            }
+        };
+        lines.push(Spans::from(Span::styled(status, theme.info)));
+        lines.push(Spans::from(Span::styled(
+            format!(
+                "[Enter] {}  [{}] Back",
+                if daily.attempted {
+                    "Practice it"
+                } else {
+                    "Play"
+                },
+                key(Action::Back)
+            ),
+            theme.border,
+        )));
+
+        let height = lines.len() as u16;
+        if render_too_small(f, MIN_WIDTH, height, theme) {
+            return;
+        }
+        let paragraph = Paragraph::new(lines).alignment(Alignment::Center);
+        f.render_widget(paragraph, centered_rect(f.size(), f.size().width, height));
+    })
+}
+
+/// Shows the challenge of the day, the same paragraph for everyone with the same databases.
+pub fn create_daily_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let paragraphs = match load_paragraphs() {
+        Ok(paragraphs) => paragraphs,
+        Err(err) => {
+            return create_error_window(
+                format!("The paragraphs couldn't be loaded\n{}", err),
+                create_main_menu_window,
+            )
+        }
+    };
+    let today = Local::now().naive_local().date();
+    let paragraph = match daily_paragraph(today, &paragraphs) {
+        Some(paragraph) => paragraph,
+        None => {
+            return create_error_window(
+                "There are no paragraphs for the daily challenge\nAdd some to the databases"
+                    .to_string(),
+                create_main_menu_window,
+            )
+        }
+    };
+    state.daily = Some(Daily::load(&state.player.user_name, today, paragraph));
+
+    let mut commands = HashMap::new();
+    add_action_commands(
+        &mut commands,
+        state.config.key_bindings.keys(Action::Back),
+        create_main_menu_window,
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Enter),
+        WindowCommand::new_key_command(KeyCode::Enter, Box::new(create_daily_practice_window)),
+    );
+    Some(Window {
+        ui: Box::new(daily_window),
+        commands,
+    })
+}
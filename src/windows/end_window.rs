@@ -1,50 +1,65 @@
-use crate::get_app_path;
+use crate::save_run;
+use crate::theme::Theme;
 use crate::windows::*;
 use crate::TraceRun;
-use crate::{State, Window, WindowCommand};
-use crossterm::event::KeyCode;
-use std::io::Write;
+use crate::{add_action_commands, config::Action, State, Window, WindowRender};
+use crate::{CharStatus, ParagraphChar};
 use std::{collections::HashMap, rc::Rc};
-use tui::text::Text;
+use tui::text::{Span, Spans, Text};
 use tui::widgets::Row;
 use tui::widgets::Table;
 use tui::{
     backend::Backend,
+    layout::Rect,
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Paragraph},
+    style::Modifier,
+    widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
 
-fn end_window<B: Backend>(state: Rc<State>) -> Box<dyn Fn(&mut Frame<B>)> {
+const MIN_WIDTH: u16 = 56;
+const MIN_HEIGHT: u16 = 11;
+
+fn end_window<B: Backend>(state: Rc<State>) -> WindowRender<B> {
     Box::new(move |f| {
         let TraceRun {
             seconds,
             accuracy,
             wpm,
             total_points,
+            ..
         } = state.create_run();
 
         let formatted_seconds = format!("{:.2}", seconds);
         let formatted_accuracy = format!("{:.2} %", accuracy * 100.0);
         let formatted_wpm = format!("{:.2}", wpm);
         let formatted_total_points = format!("{:.2}", total_points);
+        let theme = &state.config.theme;
+        if render_too_small(f, MIN_WIDTH, MIN_HEIGHT, theme) {
+            return;
+        }
+        let size = f.size();
+        let container = centered_rect(
+            size,
+            (size.width * 3 / 4).clamp(MIN_WIDTH, std::cmp::max(MIN_WIDTH, 110)),
+            (size.height * 3 / 4).clamp(MIN_HEIGHT, std::cmp::max(MIN_HEIGHT, 36)),
+        );
         let layout = Layout::default()
             .direction(Direction::Vertical)
-            .margin(8)
             .constraints([
-                Constraint::Percentage(35),
-                Constraint::Percentage(50),
-                Constraint::Percentage(5),
+                Constraint::Length(5),
+                Constraint::Min(3),
+                Constraint::Length(3),
             ])
-            .split(f.size());
+            .split(container);
         let header_layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
                 [
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(40),
-                    Constraint::Percentage(40),
+                    Constraint::Length(2),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                    Constraint::Min(0),
                 ]
                 .as_ref(),
             )
@@ -54,44 +69,57 @@ fn end_window<B: Backend>(state: Rc<State>) -> Box<dyn Fn(&mut Frame<B>)> {
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
             .split(header_layout[2]);
 
-        let control_buttons = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(
-                [
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(1)
-                ]
-                .as_ref(),
-            )
-            .split(layout[2]);
-
-        let thanks = Paragraph::new("Thank you for playing!").alignment(Alignment::Center);
+        let thanks = match &state.daily {
+            Some(daily) if daily.scored => format!(
+                "Daily challenge · #{} of {} today · Streak: {}",
+                daily.rank(&state.player.user_name).unwrap_or_default(),
+                daily.leaderboard.len(),
+                daily.streak
+            ),
+            Some(_) => "Daily challenge · Practice, only the first attempt is scored".to_string(),
+            None if state.player.statistics.failed => format!(
+                "Run failed · {} errors · It isn't counted in your statistics",
+                state.player.statistics.total_error_count
+            ),
+            None => "Thank you for playing!".to_string(),
+        };
+        let thanks_style = if state.player.statistics.failed {
+            theme.error
+        } else {
+            theme.text
+        };
+        let thanks = Paragraph::new(thanks)
+            .style(thanks_style)
+            .alignment(Alignment::Center);
         f.render_widget(thanks, header_layout[0]);
 
-        let title = Paragraph::new(Text::raw(&state.paragraph.title))
-            .style(Style::default().fg(Color::LightCyan))
+        let title = match &state.book {
+            Some(book) => format!(
+                "{} · {} of {}",
+                book.title,
+                book.position + 1,
+                book.paragraphs.len()
+            ),
+            None => state.paragraph.title.clone(),
+        };
+        let title = Paragraph::new(Text::raw(title))
+            .style(theme.info)
             .alignment(Alignment::Center);
         f.render_widget(title, header_layout[1]);
 
         let author = Paragraph::new(Text::raw(&state.paragraph.author))
-            .style(Style::default().fg(Color::LightCyan))
+            .style(theme.info)
             .alignment(Alignment::Center);
         f.render_widget(author, info_layout[0]);
 
         let date = Paragraph::new(Text::raw(&state.paragraph.date))
-            .style(Style::default().fg(Color::LightCyan))
+            .style(theme.info)
             .alignment(Alignment::Center);
         f.render_widget(date, info_layout[1]);
 
         let table = Table::new(vec![
-            Row::new(vec!["#", "Name", "Points", "Time (s)", "Accuracy", "WPM"]).style(
-                Style::default()
-                    .fg(Color::LightCyan)
-                    .add_modifier(Modifier::BOLD),
-            ),
+            Row::new(vec!["#", "Name", "Points", "Time (s)", "Accuracy", "WPM"])
+                .style(theme.info.add_modifier(Modifier::BOLD)),
             Row::new(vec![
                 "1",
                 "You",
@@ -114,64 +142,267 @@ fn end_window<B: Backend>(state: Rc<State>) -> Box<dyn Fn(&mut Frame<B>)> {
             .as_ref(),
         )
         .column_spacing(1);
-        f.render_widget(table, layout[1]);
+        let review_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(layout[1]);
+        f.render_widget(table, review_layout[0]);
+        render_review(f, review_layout[1], &state);
 
-        create_centered_button("R", "eset", control_buttons[0], f);
-        create_centered_button("M", "enu", control_buttons[1], f);
-        create_centered_button("S", "tatistics", control_buttons[2], f);
-        create_centered_button("E", "xit", control_buttons[3], f);
+        let key_bindings = &state.config.key_bindings;
+        let mut buttons = vec![];
+        if can_continue(&state) {
+            buttons.push(key_bindings.button_label(Action::Continue, "Continue"));
+        }
+        buttons.extend([
+            key_bindings.button_label(Action::Reset, "Reset"),
+            key_bindings.button_label(Action::Menu, "Menu"),
+            key_bindings.button_label(Action::Statistics, "Statistics"),
+            key_bindings.button_label(Action::Exit, "Exit"),
+        ]);
+        let control_buttons = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                buttons
+                    .iter()
+                    .map(|_| Constraint::Ratio(1, buttons.len() as u32))
+                    .collect::<Vec<_>>(),
+            )
+            .split(layout[2]);
+        for (i, (activator, rest)) in buttons.iter().enumerate() {
+            create_centered_button(activator, rest, control_buttons[i], f, theme);
+        }
     })
 }
 
+/// Most missed chars and words shown in the review.
+const MOST_MISSED: usize = 5;
+
+/// Words of the run, each with the space after it and the extra chars typed in it.
+fn split_words(chars: &[ParagraphChar]) -> Vec<&[ParagraphChar]> {
+    let mut words = vec![];
+    let mut start = 0;
+    for (i, c) in chars.iter().enumerate() {
+        if c.character == ' ' && c.status != CharStatus::Extra || i + 1 == chars.len() {
+            words.push(&chars[start..=i]);
+            start = i + 1;
+        }
+    }
+    words
+}
+
+fn expected_text(word: &[ParagraphChar]) -> String {
+    word.iter()
+        .filter(|c| c.status != CharStatus::Extra)
+        .map(|c| c.character)
+        .collect()
+}
+
+/// The paragraph as a word diff of the run: words typed wrong show what was typed crossed out,
+/// an arrow and what was expected.
+fn mistake_diff<'a>(chars: &[ParagraphChar], theme: &Theme) -> Vec<Span<'a>> {
+    let mut spans = vec![];
+    for word in split_words(chars) {
+        let expected = expected_text(word);
+        let wrong = word
+            .iter()
+            .any(|c| matches!(c.status, CharStatus::Wrong | CharStatus::Extra));
+        if wrong {
+            let typed: String = word
+                .iter()
+                .map(|c| c.typed.unwrap_or(c.character))
+                .collect();
+            spans.push(Span::styled(
+                typed.trim_end().to_string(),
+                theme.wrong.add_modifier(Modifier::CROSSED_OUT),
+            ));
+            spans.push(Span::styled("→", theme.info));
+            spans.push(Span::styled(expected, theme.correct));
+        } else {
+            spans.push(Span::styled(expected, theme.text));
+        }
+    }
+    spans
+}
+
+/// The paragraph with every char that was typed wrong during the run, even if it was fixed.
+fn mistyped_chars<'a>(chars: &[ParagraphChar], theme: &Theme) -> Vec<Span<'a>> {
+    chars
+        .iter()
+        .filter(|c| c.status != CharStatus::Extra)
+        .map(|c| match (c.mistyped, c.character) {
+            (true, ' ') => Span::styled("·", theme.wrong_space),
+            (true, character) => Span::styled(
+                character.to_string(),
+                theme.wrong.add_modifier(Modifier::UNDERLINED),
+            ),
+            (false, character) => Span::styled(character.to_string(), theme.text),
+        })
+        .collect()
+}
+
+/// The values counted the most, with their count, most counted first and then in the order
+/// they were found.
+fn most_counted(values: Vec<String>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = vec![];
+    for value in values {
+        match counts.iter_mut().find(|(counted, _)| *counted == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts.truncate(MOST_MISSED);
+    counts
+}
+
+fn missed_line<'a>(label: &'a str, missed: Vec<(String, usize)>, theme: &Theme) -> Spans<'a> {
+    let mut spans = vec![Span::styled(format!("{:<7}", label), theme.info)];
+    if missed.is_empty() {
+        spans.push(Span::styled("None", theme.correct));
+    }
+    for (value, count) in missed {
+        spans.push(Span::styled(
+            value,
+            theme.wrong.add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(format!(" ×{}  ", count), theme.text));
+    }
+    Spans::from(spans)
+}
+
+/// Where the run went wrong: the mistyped chars, the most missed chars and words, and the speed
+/// of every second with its hesitations and error bursts.
+fn render_review<B: Backend>(f: &mut Frame<B>, area: Rect, state: &State) {
+    let theme = &state.config.theme;
+    let layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+    let side_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0)].as_ref())
+        .split(layout[1]);
+
+    // Blind runs show what was typed, the rest already saw it.
+    let (title, text) = if state.rules.blind {
+        ("Your mistakes", mistake_diff(&state.chars, theme))
+    } else {
+        ("Mistyped chars", mistyped_chars(&state.chars, theme))
+    };
+    let paragraph = Paragraph::new(Spans::from(text))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border)
+                .title(title),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, layout[0]);
+
+    let missed_chars = state
+        .chars
+        .iter()
+        .filter(|c| c.mistyped && c.status != CharStatus::Extra)
+        .map(|c| match c.character {
+            ' ' => "Space".to_string(),
+            character => character.to_string(),
+        })
+        .collect();
+    let missed_words = split_words(&state.chars)
+        .into_iter()
+        .filter(|word| word.iter().any(|c| c.mistyped))
+        .map(|word| expected_text(word).trim_end().to_string())
+        .collect();
+    let missed = Paragraph::new(vec![
+        missed_line("Chars", most_counted(missed_chars), theme),
+        missed_line("Words", most_counted(missed_words), theme),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border)
+            .title("Most missed"),
+    );
+    f.render_widget(missed, side_layout[0]);
+
+    render_timeline(
+        f,
+        side_layout[1],
+        &state.create_run(),
+        "WPM per second",
+        theme,
+    );
+}
+
+fn can_continue(state: &State) -> bool {
+    !state.player.statistics.failed && state.book.as_ref().is_some_and(|book| !book.is_finished())
+}
+
 pub fn create_end_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
     let run = state.create_run();
-    let path = get_app_path(".runs.csv");
-    let csv = format!("\n{}", run.to_csv());
-    let mut file = match std::fs::OpenOptions::new()
-        .write(true)
-        .append(true)
-        .open(&path)
+    if let Err(err) = save_run(&state.player.user_name, &run) {
+        return create_error_window(
+            format!("Sorry, your run couldn't be saved\n{}", err),
+            create_main_menu_window,
+        );
+    }
+
+    if let Some(daily) = state.daily.as_mut().filter(|daily| daily.scored) {
+        if let Err(err) = daily.finish_attempt(&state.player.user_name, &run) {
+            return create_error_window(
+                format!("Sorry, your daily challenge couldn't be saved\n{}", err),
+                create_main_menu_window,
+            );
+        }
+    }
+
+    // A failed run doesn't move the book forward.
+    if let Some(book) = state
+        .book
+        .as_ref()
+        .filter(|_| !state.player.statistics.failed)
     {
-        Ok(f) => f,
-        Err(_) => {
-            let mut file = std::fs::OpenOptions::new()
-                .write(true)
-                .create(true)
-                .open(&path)
-                .unwrap();
-            file.write("wpm,accuracy,total_points,seconds".as_bytes());
-            file
+        if let Err(err) = book.save_progress(&state.player.user_name) {
+            return create_error_window(
+                format!("Sorry, your place in the book couldn't be saved\n{}", err),
+                create_main_menu_window,
+            );
         }
-    };
-    file.write(csv.as_bytes());
-    file.flush();
+    }
 
+    let key_bindings = &state.config.key_bindings;
+    let mut commands = HashMap::new();
+    add_action_commands(&mut commands, key_bindings.keys(Action::Back), |_| None);
+    add_action_commands(&mut commands, key_bindings.keys(Action::Exit), |_| None);
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Reset),
+        create_empty_practice_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Menu),
+        create_main_menu_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Statistics),
+        create_statistics_window,
+    );
+    if can_continue(state) {
+        add_action_commands(
+            &mut commands,
+            key_bindings.keys(Action::Continue),
+            |state| {
+                let mut book = state.book.take()?;
+                book.position += 1;
+                create_book_practice_window(state, book)
+            },
+        );
+    }
     Some(Window {
         ui: Box::new(end_window),
-        commands: HashMap::from([
-            (
-                KeyCode::Char('e'),
-                WindowCommand::new_char_command('e', Box::new(|_| None)),
-            ),
-            (
-                KeyCode::Esc,
-                WindowCommand {
-                    activator_key: KeyCode::Esc,
-                    action: Box::new(|_| None),
-                },
-            ),
-            (
-                KeyCode::Char('r'),
-                WindowCommand::new_char_command('r', Box::new(create_empty_practice_window)),
-            ),
-            (
-                KeyCode::Char('m'),
-                WindowCommand::new_char_command('m', Box::new(create_main_menu_window)),
-            ),
-            (
-                KeyCode::Char('s'),
-                WindowCommand::new_char_command('s', Box::new(create_statistics_window)),
-            ),
-        ]),
+        commands,
     })
 }
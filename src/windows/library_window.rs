@@ -0,0 +1,424 @@
+use crate::config::key_to_string;
+use crate::database::load_paragraphs;
+use crate::library::{Book, BookProgress, LibraryMarks, LibraryState};
+use crate::windows::*;
+use crate::AppParagraph;
+use crate::State;
+use crate::Window;
+use crate::WindowAction;
+use crate::WindowCommand;
+use crate::WindowRender;
+use crate::{add_action_commands, add_to_commands, config::Action, generate_all_chars};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap};
+
+const MIN_WIDTH: u16 = 50;
+const MIN_HEIGHT: u16 = 12;
+
+/// Paragraphs skipped by the page actions.
+const PAGE_JUMP: usize = 10;
+
+fn library_window<B: 'static + Backend>(state: Rc<State>) -> WindowRender<B> {
+    Box::new(move |f| {
+        let theme = &state.config.theme;
+        if render_too_small(f, MIN_WIDTH, MIN_HEIGHT, theme) {
+            return;
+        }
+        let library = &state.library;
+        let listed = library.listed();
+        let key = |action: Action| {
+            state
+                .config
+                .key_bindings
+                .keys(action)
+                .first()
+                .map(|key| key_to_string(*key))
+                .unwrap_or_default()
+        };
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                    Constraint::Length(2),
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
+
+        let search_style = if library.searching {
+            theme.highlight
+        } else {
+            theme.text
+        };
+        let header = Paragraph::new(Spans::from(vec![
+            Span::styled(
+                format!(
+                    "Library · {} · {} paragraphs",
+                    library.filter.label(),
+                    listed.len()
+                ),
+                theme.title.add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("   Search: ", theme.text),
+            Span::styled(
+                if library.searching {
+                    format!("{}▏", library.query)
+                } else {
+                    library.query.clone()
+                },
+                search_style,
+            ),
+        ]))
+        .alignment(Alignment::Center);
+        f.render_widget(header, layout[0]);
+
+        let body = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)].as_ref())
+            .split(layout[1]);
+
+        let rows: Vec<Row> = listed
+            .iter()
+            .map(|i| {
+                let paragraph = &library.paragraphs[*i];
+                let mark = if library.marks.is_hidden(paragraph) {
+                    "·"
+                } else if library.marks.is_favourite(paragraph) {
+                    "★"
+                } else {
+                    " "
+                };
+                Row::new(vec![
+                    mark.to_string(),
+                    paragraph.title.clone(),
+                    paragraph.author.clone(),
+                    paragraph.date.clone(),
+                    paragraph.content.chars().count().to_string(),
+                ])
+                .style(theme.text)
+            })
+            .collect();
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["", "Title", "Author", "Date", "Chars"])
+                    .style(theme.info.add_modifier(Modifier::BOLD)),
+            )
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border),
+            )
+            .widths(
+                [
+                    Constraint::Length(1),
+                    Constraint::Percentage(36),
+                    Constraint::Percentage(24),
+                    Constraint::Percentage(22),
+                    Constraint::Percentage(18),
+                ]
+                .as_ref(),
+            )
+            .column_spacing(1)
+            .highlight_style(
+                theme
+                    .highlight
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            );
+        let mut table_state = TableState::default();
+        if !listed.is_empty() {
+            table_state.select(Some(library.cursor));
+        }
+        f.render_stateful_widget(table, body[0], &mut table_state);
+
+        let preview = match listed.get(library.cursor) {
+            Some(i) => {
+                let paragraph = &library.paragraphs[*i];
+                let book: Vec<&AppParagraph> = library
+                    .paragraphs
+                    .iter()
+                    .filter(|other| other.title == paragraph.title)
+                    .collect();
+                let book_position = book
+                    .iter()
+                    .position(|other| std::ptr::eq(*other, paragraph))
+                    .unwrap_or(0);
+                let next = library.books.position(&paragraph.title, book.len());
+                vec![
+                    Spans::from(Span::styled(
+                        paragraph.title.clone(),
+                        theme.title.add_modifier(Modifier::BOLD),
+                    )),
+                    Spans::from(Span::styled(
+                        format!(
+                            "{} · {} · {}",
+                            paragraph.author, paragraph.date, paragraph.language
+                        ),
+                        theme.info,
+                    )),
+                    Spans::from(Span::styled(
+                        format!(
+                            "Difficulty: {} ({:.0})",
+                            paragraph.difficulty().label(),
+                            paragraph.difficulty_score()
+                        ),
+                        theme.info,
+                    )),
+                    Spans::from(Span::styled(
+                        format!(
+                            "Paragraph {} of {} · Book continues at {}",
+                            book_position + 1,
+                            book.len(),
+                            next + 1
+                        ),
+                        theme.info,
+                    )),
+                    Spans::from(""),
+                    Spans::from(Span::styled(paragraph.content.clone(), theme.text)),
+                ]
+            }
+            None => vec![Spans::from(Span::styled(
+                "No paragraphs match",
+                theme.border,
+            ))],
+        };
+        let preview = Paragraph::new(preview)
+            .block(
+                Block::default()
+                    .title("Preview")
+                    .borders(Borders::ALL)
+                    .border_style(theme.border),
+            )
+            .wrap(Wrap { trim: true });
+        f.render_widget(preview, body[1]);
+
+        let status = match &library.message {
+            Some(message) => Span::styled(message.clone(), theme.error),
+            None => Span::styled("", Style::default()),
+        };
+        let help = if library.searching {
+            format!("[Enter] Done  [{}] Clear search", key(Action::Back))
+        } else {
+            format!(
+                "[Up/Down] Move  [Enter] Practice  [{}] Book  [{}] Search  [{}] Favourite  [{}] Hide  [{}] Filter  [{}] Back",
+                key(Action::Book),
+                key(Action::Search),
+                key(Action::ToggleFavourite),
+                key(Action::ToggleHidden),
+                key(Action::CycleFilter),
+                key(Action::Back),
+            )
+        };
+        let footer = Paragraph::new(vec![
+            Spans::from(status),
+            Spans::from(Span::styled(help, theme.border)),
+        ])
+        .alignment(Alignment::Center);
+        f.render_widget(footer, layout[2]);
+    })
+}
+
+/// Moves the cursor by `offset`, staying inside the listed paragraphs.
+fn move_cursor(library: &mut LibraryState, offset: isize) {
+    let last = library.listed().len().saturating_sub(1);
+    library.cursor = (library.cursor as isize + offset).clamp(0, last as isize) as usize;
+}
+
+/// Toggles the selected paragraph in the marks returned by `marks`, and saves them.
+fn toggle_mark(state: &mut State, marks: fn(&mut LibraryMarks) -> &mut BTreeSet<String>) {
+    let library = &mut state.library;
+    let paragraph = match library.listed().get(library.cursor) {
+        Some(i) => library.paragraphs[*i].clone(),
+        None => return,
+    };
+    LibraryMarks::toggle(marks(&mut library.marks), &paragraph);
+    library.message = library
+        .marks
+        .save(&state.player.user_name)
+        .err()
+        .map(|err| format!("Couldn't save the library: {}", err));
+    // The paragraph can leave the list, like when hiding it.
+    move_cursor(library, 0);
+}
+
+fn handle_search_char<B: 'static + Backend>(c: char) -> WindowAction<B> {
+    Box::new(move |state: &mut State| {
+        state.library.query.push(c);
+        state.library.cursor = 0;
+        create_loaded_library_window(state)
+    })
+}
+
+/// Opens the library with every paragraph of the database.
+pub fn create_library_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let paragraphs = match load_paragraphs() {
+        Ok(paragraphs) => paragraphs,
+        Err(err) => {
+            return create_error_window(
+                format!("The paragraphs couldn't be loaded\n{}", err),
+                create_main_menu_window,
+            )
+        }
+    };
+    state.library = LibraryState {
+        paragraphs,
+        marks: LibraryMarks::load(&state.player.user_name),
+        books: BookProgress::load(&state.player.user_name),
+        ..LibraryState::default()
+    };
+    create_loaded_library_window(state)
+}
+
+fn create_loaded_library_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let key_bindings = &state.config.key_bindings;
+    let mut commands = HashMap::new();
+
+    if state.library.searching {
+        add_action_commands(&mut commands, key_bindings.keys(Action::Back), |state| {
+            state.library.query.clear();
+            state.library.searching = false;
+            create_loaded_library_window(state)
+        });
+        commands.insert(
+            KeyEvent::from(KeyCode::Enter),
+            WindowCommand::new_key_command(
+                KeyCode::Enter,
+                Box::new(|state: &mut State| {
+                    state.library.searching = false;
+                    create_loaded_library_window(state)
+                }),
+            ),
+        );
+        commands.insert(
+            KeyEvent::from(KeyCode::Backspace),
+            WindowCommand::new_key_command(
+                KeyCode::Backspace,
+                Box::new(|state: &mut State| {
+                    state.library.query.pop();
+                    state.library.cursor = 0;
+                    create_loaded_library_window(state)
+                }),
+            ),
+        );
+        // Typing the query uses every char, even the ones bound to actions.
+        let chars = generate_all_chars();
+        add_to_commands(&mut commands, &chars, Box::new(handle_search_char));
+        return Some(Window {
+            ui: Box::new(library_window),
+            commands,
+        });
+    }
+
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Back),
+        create_main_menu_window,
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Up),
+        WindowCommand::new_key_command(
+            KeyCode::Up,
+            Box::new(|state: &mut State| {
+                move_cursor(&mut state.library, -1);
+                create_loaded_library_window(state)
+            }),
+        ),
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Down),
+        WindowCommand::new_key_command(
+            KeyCode::Down,
+            Box::new(|state: &mut State| {
+                move_cursor(&mut state.library, 1);
+                create_loaded_library_window(state)
+            }),
+        ),
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::PreviousPage),
+        |state| {
+            move_cursor(&mut state.library, -(PAGE_JUMP as isize));
+            create_loaded_library_window(state)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::NextPage),
+        |state| {
+            move_cursor(&mut state.library, PAGE_JUMP as isize);
+            create_loaded_library_window(state)
+        },
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Enter),
+        WindowCommand::new_key_command(
+            KeyCode::Enter,
+            Box::new(|state: &mut State| {
+                let library = &state.library;
+                match library.listed().get(library.cursor) {
+                    Some(i) => {
+                        let paragraph = library.paragraphs[*i].clone();
+                        create_paragraph_practice_window(state, paragraph)
+                    }
+                    None => create_loaded_library_window(state),
+                }
+            }),
+        ),
+    );
+    add_action_commands(&mut commands, key_bindings.keys(Action::Book), |state| {
+        let library = &state.library;
+        let book = library.listed().get(library.cursor).and_then(|i| {
+            Book::open(
+                &library.paragraphs,
+                &library.paragraphs[*i].title,
+                &library.books,
+            )
+        });
+        match book {
+            Some(book) => create_book_practice_window(state, book),
+            None => create_loaded_library_window(state),
+        }
+    });
+    add_action_commands(&mut commands, key_bindings.keys(Action::Search), |state| {
+        state.library.searching = true;
+        create_loaded_library_window(state)
+    });
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::ToggleFavourite),
+        |state| {
+            toggle_mark(state, |marks| &mut marks.favourites);
+            create_loaded_library_window(state)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::ToggleHidden),
+        |state| {
+            toggle_mark(state, |marks| &mut marks.hidden);
+            create_loaded_library_window(state)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::CycleFilter),
+        |state| {
+            state.library.filter = state.library.filter.next();
+            state.library.cursor = 0;
+            create_loaded_library_window(state)
+        },
+    );
+    Some(Window {
+        ui: Box::new(library_window),
+        commands,
+    })
+}
@@ -1,83 +1,110 @@
+use crate::database::{languages, load_paragraphs};
 use crate::windows::*;
-use crate::{get_app_path, State, Window, WindowCommand};
-use crossterm::event::KeyCode;
+use crate::{add_action_commands, config::Action, State, Window, WindowRender};
 
 use std::{collections::HashMap, rc::Rc};
-use tui::{backend::Backend, layout::Constraint, layout::Layout, Frame};
+use tui::backend::Backend;
 
-pub fn main_menu_window<B: Backend>(_: Rc<State>) -> Box<dyn Fn(&mut Frame<B>)> {
-    Box::new(|f| {
-        let game_title = "▀█▀ █▀█ ▄▀█ █▀▀ █▀▀\n░█░ █▀▄ █▀█ █▄▄ ██▄";
+pub fn main_menu_window<B: Backend>(state: Rc<State>) -> WindowRender<B> {
+    Box::new(move |f| {
+        let game_title = "▀█▀ █▀█ ▄▀█ █▀▀ █▀▀\n░█░ █▀▄ █▀█ █▄▄ ██▄";
 
-        let container = Layout::default()
-            .horizontal_margin(f.size().width / 4)
-            .vertical_margin(f.size().height / 4)
-            .constraints([Constraint::Percentage(1)].as_ref())
-            .split(f.size());
-
-        let buttons = vec![
-            ("P", "ractice"),
-            ("S", "tatistics"),
-            ("M", "ultiplayer"),
-            ("E", "xit"),
+        let key_bindings = &state.config.key_bindings;
+        let labels = [
+            key_bindings.button_label(Action::Practice, "Practice"),
+            key_bindings.button_label(Action::Daily, "Daily"),
+            key_bindings.button_label(Action::Library, "Library"),
+            key_bindings.button_label(
+                Action::Language,
+                &format!("Language: {}", state.selection.languages_label()),
+            ),
+            key_bindings.button_label(Action::Options, "Options"),
+            key_bindings.button_label(Action::Statistics, "Statistics"),
+            key_bindings.button_label(Action::Multiplayer, "Multiplayer"),
+            key_bindings.button_label(Action::Profiles, "User"),
+            key_bindings.button_label(Action::Exit, "Exit"),
         ];
+        let buttons = labels
+            .iter()
+            .map(|(activator, rest)| (activator.as_str(), rest.as_str()))
+            .collect();
 
-        create_menu(f, container[0], game_title, buttons);
+        create_menu(f, game_title, buttons, &state.config.theme);
     })
 }
 
-pub fn create_main_menu_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
-    let path = get_app_path(".user");
-    let user_name = match std::fs::read(path) {
-        Ok(bytes) => match std::str::from_utf8(&bytes) {
-            Ok(s) => s.to_string(),
-            Err(_) => return create_user_window(state),
-        },
-        Err(_) => return create_user_window(state),
+/// Picks the next language of the databases for practice, the same as the Options window does.
+fn cycle_language<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let paragraphs = match load_paragraphs() {
+        Ok(paragraphs) => paragraphs,
+        Err(err) => {
+            return create_error_window(
+                format!("The paragraphs couldn't be loaded\n{}", err),
+                create_main_menu_window,
+            )
+        }
     };
-    state.player.user_name = user_name;
+    state.selection.cycle_language(&languages(&paragraphs), 1);
+    if let Err(err) = state.selection.save(&state.player.user_name) {
+        return create_error_window(
+            format!("Couldn't save the options: {}", err),
+            create_main_menu_window,
+        );
+    }
+    create_main_menu_window(state)
+}
+
+pub fn create_main_menu_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    if state.player.user_name.is_empty() {
+        return create_user_window(state);
+    }
+
+    let key_bindings = &state.config.key_bindings;
+    let mut commands = HashMap::new();
+    add_action_commands(&mut commands, key_bindings.keys(Action::Back), |_| None);
+    add_action_commands(&mut commands, key_bindings.keys(Action::Exit), |_| None);
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Practice),
+        create_empty_practice_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Daily),
+        create_daily_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Library),
+        create_library_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Language),
+        cycle_language,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Options),
+        create_options_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Statistics),
+        create_statistics_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Multiplayer),
+        create_multiplayer_menu_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Profiles),
+        create_user_window,
+    );
     Some(Window {
         ui: Box::new(main_menu_window),
-        commands: HashMap::from([
-            (
-                KeyCode::Char('e'),
-                WindowCommand::new_char_command('e', Box::new(|_| None)),
-            ),
-            (
-                KeyCode::Char('E'),
-                WindowCommand::new_char_command('E', Box::new(|_| None)),
-            ),
-            (
-                KeyCode::Esc,
-                WindowCommand {
-                    activator_key: KeyCode::Esc,
-                    action: Box::new(|_| None),
-                },
-            ),
-            (
-                KeyCode::Char('p'),
-                WindowCommand::new_char_command('p', Box::new(create_empty_practice_window)),
-            ),
-            (
-                KeyCode::Char('P'),
-                WindowCommand::new_char_command('P', Box::new(create_empty_practice_window)),
-            ),
-            (
-                KeyCode::Char('s'),
-                WindowCommand::new_char_command('s', Box::new(create_statistics_window)),
-            ),
-            (
-                KeyCode::Char('S'),
-                WindowCommand::new_char_command('S', Box::new(create_statistics_window)),
-            ),
-            (
-                KeyCode::Char('m'),
-                WindowCommand::new_char_command('m', Box::new(create_multiplayer_menu_window)),
-            ),
-            (
-                KeyCode::Char('M'),
-                WindowCommand::new_char_command('M', Box::new(create_multiplayer_menu_window)),
-            ),
-        ]),
+        commands,
     })
 }
@@ -1,33 +1,28 @@
 use crate::windows::*;
 use crate::State;
 use crate::Window;
-use crate::WindowCommand;
-use crossterm::event::KeyCode;
-use pnet::datalink::NetworkInterface;
-use pnet::ipnetwork::IpNetwork;
+use crate::WindowRender;
+use crate::{add_action_commands, config::Action};
 use std::collections::HashMap;
 use std::rc::Rc;
 use tui::backend::Backend;
 use tui::Frame;
 
-fn multiplayer_menu_window<B: 'static + Backend>(_: Rc<State>) -> Box<dyn Fn(&mut Frame<B>)> {
-    Box::new(|f: &mut Frame<B>| {
+fn multiplayer_menu_window<B: 'static + Backend>(state: Rc<State>) -> WindowRender<B> {
+    Box::new(move |f: &mut Frame<B>| {
         let game_title = "█▀▄▀█ █░█ █░░ ▀█▀ █ █▀█ █░░ ▄▀█ █▄█ █▀▀ █▀█\n█░▀░█ █▄█ █▄▄ ░█░ █ █▀▀ █▄▄ █▀█ ░█░ ██▄ █▀▄";
 
-        let container = Layout::default()
-            .horizontal_margin(f.size().width / 4)
-            .vertical_margin(f.size().height / 4)
-            .constraints([Constraint::Percentage(1)])
-            .split(f.size());
+        let key_bindings = &state.config.key_bindings;
+        let labels = [
+            key_bindings.button_label(Action::JoinRoom, "Join Room"),
+            key_bindings.button_label(Action::NewRoom, "New Room"),
+        ];
+        let buttons = labels
+            .iter()
+            .map(|(activator, rest)| (activator.as_str(), rest.as_str()))
+            .collect();
 
-        let buttons = vec![("J", "oin Room"), ("N", "ew Room")];
-
-        let padding = Padding {
-            width: container[0].width / 5,
-            height: container[0].height / 8,
-        };
-
-        create_menu_with_pad(f, container[0], game_title, buttons, padding);
+        create_menu(f, game_title, buttons, &state.config.theme);
         // let ip_address = local_ip_address::local_ip().unwrap();
         // let network_interfaces = local_ip_address::list_afinet_netifas().unwrap();
 
@@ -65,25 +60,28 @@ fn multiplayer_menu_window<B: 'static + Backend>(_: Rc<State>) -> Box<dyn Fn(&mu
     })
 }
 
-pub fn create_multiplayer_menu_window<B: 'static + Backend>(_: &mut State) -> Option<Window<B>> {
+pub fn create_multiplayer_menu_window<B: 'static + Backend>(
+    state: &mut State,
+) -> Option<Window<B>> {
+    let key_bindings = &state.config.key_bindings;
+    let mut commands = HashMap::new();
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Back),
+        create_main_menu_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::NewRoom),
+        create_mp_create_server_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::JoinRoom),
+        create_mp_join_server_window,
+    );
     Some(Window {
         ui: Box::new(multiplayer_menu_window),
-        commands: HashMap::from([
-            (
-                KeyCode::Esc,
-                WindowCommand {
-                    activator_key: KeyCode::Esc,
-                    action: Box::new(create_main_menu_window),
-                },
-            ),
-            (
-                KeyCode::Char('n'),
-                WindowCommand::new_char_command('n', Box::new(create_mp_create_server_window)),
-            ),
-            (
-                KeyCode::Char('j'),
-                WindowCommand::new_char_command('j', Box::new(create_mp_join_server_window)),
-            ),
-        ]),
+        commands,
     })
 }
@@ -0,0 +1,268 @@
+use crate::config::{key_to_string, Action};
+use crate::database::{languages, load_paragraphs};
+use crate::library::{OptionsState, PracticeOption, MAX_SEED_DIGITS};
+use crate::windows::*;
+use crate::State;
+use crate::Window;
+use crate::WindowAction;
+use crate::WindowCommand;
+use crate::WindowRender;
+use crate::{add_action_commands, add_to_commands};
+use crossterm::event::{KeyCode, KeyEvent};
+use rand::Rng;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::layout::Alignment;
+use tui::style::Modifier;
+use tui::text::{Span, Spans};
+use tui::widgets::{Paragraph, Wrap};
+
+const MIN_WIDTH: u16 = 42;
+/// The help wraps to three lines at the minimum width.
+const MIN_HEIGHT: u16 = 16;
+/// Room left at the end of the wrapped lines of the help, as long as its longest word.
+const HELP_WRAP_SLACK: usize = 12;
+
+fn options_window<B: 'static + Backend>(state: Rc<State>) -> WindowRender<B> {
+    Box::new(move |f| {
+        let theme = &state.config.theme;
+        if render_too_small(f, MIN_WIDTH, MIN_HEIGHT, theme) {
+            return;
+        }
+        let selection = &state.selection;
+        let key = |action: Action| {
+            state
+                .config
+                .key_bindings
+                .keys(action)
+                .first()
+                .map(|key| key_to_string(*key))
+                .unwrap_or_default()
+        };
+
+        let mut lines = vec![
+            Spans::from(Span::styled(
+                "Practice options",
+                theme.title.add_modifier(Modifier::BOLD),
+            )),
+            Spans::from(""),
+        ];
+        for (i, option) in PracticeOption::ALL.iter().enumerate() {
+            let value = match option {
+                PracticeOption::Language => selection.languages_label(),
+                PracticeOption::Difficulty => selection.difficulty_label().to_string(),
+                PracticeOption::Length => selection.length_label(),
+                PracticeOption::AvoidRepeats => on_off(!selection.allow_repeats),
+                PracticeOption::PreferNew => on_off(selection.prefer_new),
+                PracticeOption::Seed => match selection.seed {
+                    Some(seed) => format!("{} · #{}", seed, selection.seed_position + 1),
+                    None => "Off".to_string(),
+                },
+                PracticeOption::ErrorLimit => selection.error_limit_label(),
+                PracticeOption::Backspace => on_off(!selection.no_backspace),
+                PracticeOption::Blind => on_off(selection.blind),
+            };
+            // Every line is as wide so they're aligned once centered.
+            let text = format!("{:<14}{:^24}", option.label(), format!("◄ {} ►", value));
+            lines.push(if i == state.options.cursor {
+                Spans::from(Span::styled(
+                    format!("► {}", text),
+                    theme.highlight.add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Spans::from(Span::styled(format!("  {}", text), theme.text))
+            });
+        }
+        lines.push(Spans::from(""));
+        lines.push(Spans::from(Span::styled(
+            state.options.message.clone().unwrap_or_default(),
+            theme.error,
+        )));
+        let change = if PracticeOption::ALL[state.options.cursor] == PracticeOption::Seed {
+            "[Left/Right] New/Off  [0-9] Type"
+        } else {
+            "[Left/Right] Change"
+        };
+        let help = format!(
+            "[Up/Down] Move  {}  [Enter] Practice  [{}] Back",
+            change,
+            key(Action::Back)
+        );
+        let width = f.size().width as usize;
+        let help_height = if help.chars().count() <= width {
+            1
+        } else {
+            help.chars().count().div_ceil(width - HELP_WRAP_SLACK)
+        };
+
+        let options_height = lines.len() as u16;
+        let area = centered_rect(
+            f.size(),
+            f.size().width,
+            options_height + help_height as u16,
+        );
+        let paragraph = Paragraph::new(lines).alignment(Alignment::Center);
+        f.render_widget(paragraph, area);
+        // Wrapped apart so the trimmed spaces aren't the ones aligning the options.
+        let help = Paragraph::new(Span::styled(help, theme.border))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        let help_area = Rect {
+            y: area.y + options_height,
+            height: area.height.saturating_sub(options_height),
+            ..area
+        };
+        f.render_widget(help, help_area);
+    })
+}
+
+fn on_off(value: bool) -> String {
+    if value { "On" } else { "Off" }.to_string()
+}
+
+fn save_selection(state: &mut State) {
+    state.options.message = state
+        .selection
+        .save(&state.player.user_name)
+        .err()
+        .map(|err| format!("Couldn't save the options: {}", err));
+}
+
+/// Changes the selected option and saves the selection.
+fn change_option(state: &mut State, offset: isize) {
+    let selection = &mut state.selection;
+    match PracticeOption::ALL[state.options.cursor] {
+        PracticeOption::Language => selection.cycle_language(&state.options.languages, offset),
+        PracticeOption::Difficulty => selection.cycle_difficulty(offset),
+        PracticeOption::Length => selection.cycle_length(offset),
+        PracticeOption::AvoidRepeats => selection.allow_repeats = !selection.allow_repeats,
+        PracticeOption::PreferNew => selection.prefer_new = !selection.prefer_new,
+        // Short seeds are easier to share.
+        PracticeOption::Seed => selection.set_seed(match selection.seed {
+            Some(_) => None,
+            None => Some(rand::thread_rng().gen_range(0..100_000)),
+        }),
+        PracticeOption::ErrorLimit => selection.cycle_error_limit(offset),
+        PracticeOption::Backspace => selection.no_backspace = !selection.no_backspace,
+        PracticeOption::Blind => selection.blind = !selection.blind,
+    }
+    save_selection(state);
+}
+
+/// Types a digit of the seed, when it's selected.
+fn handle_digit_press<B: 'static + Backend>(digit: char) -> WindowAction<B> {
+    Box::new(move |state: &mut State| {
+        let selection = &mut state.selection;
+        if PracticeOption::ALL[state.options.cursor] == PracticeOption::Seed {
+            let mut seed = selection
+                .seed
+                .map(|seed| seed.to_string())
+                .unwrap_or_default();
+            if seed.len() < MAX_SEED_DIGITS {
+                seed.push(digit);
+                selection.set_seed(seed.parse().ok());
+                save_selection(state);
+            }
+        }
+        create_loaded_options_window(state)
+    })
+}
+
+/// Opens the options of the paragraphs picked for practice.
+pub fn create_options_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let paragraphs = match load_paragraphs() {
+        Ok(paragraphs) => paragraphs,
+        Err(err) => {
+            return create_error_window(
+                format!("The paragraphs couldn't be loaded\n{}", err),
+                create_main_menu_window,
+            )
+        }
+    };
+    state.options = OptionsState {
+        languages: languages(&paragraphs),
+        ..OptionsState::default()
+    };
+    create_loaded_options_window(state)
+}
+
+fn create_loaded_options_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let key_bindings = &state.config.key_bindings;
+    let mut commands = HashMap::new();
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Back),
+        create_main_menu_window,
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Enter),
+        WindowCommand::new_key_command(KeyCode::Enter, Box::new(create_empty_practice_window)),
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Up),
+        WindowCommand::new_key_command(
+            KeyCode::Up,
+            Box::new(|state: &mut State| {
+                state.options.cursor = state.options.cursor.saturating_sub(1);
+                create_loaded_options_window(state)
+            }),
+        ),
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Down),
+        WindowCommand::new_key_command(
+            KeyCode::Down,
+            Box::new(|state: &mut State| {
+                state.options.cursor =
+                    std::cmp::min(state.options.cursor + 1, PracticeOption::ALL.len() - 1);
+                create_loaded_options_window(state)
+            }),
+        ),
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Left),
+        WindowCommand::new_key_command(
+            KeyCode::Left,
+            Box::new(|state: &mut State| {
+                change_option(state, -1);
+                create_loaded_options_window(state)
+            }),
+        ),
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Right),
+        WindowCommand::new_key_command(
+            KeyCode::Right,
+            Box::new(|state: &mut State| {
+                change_option(state, 1);
+                create_loaded_options_window(state)
+            }),
+        ),
+    );
+    commands.insert(
+        KeyEvent::from(KeyCode::Backspace),
+        WindowCommand::new_key_command(
+            KeyCode::Backspace,
+            Box::new(|state: &mut State| {
+                let selection = &mut state.selection;
+                if PracticeOption::ALL[state.options.cursor] == PracticeOption::Seed {
+                    let mut seed = selection
+                        .seed
+                        .map(|seed| seed.to_string())
+                        .unwrap_or_default();
+                    seed.pop();
+                    selection.set_seed(seed.parse().ok());
+                    save_selection(state);
+                }
+                create_loaded_options_window(state)
+            }),
+        ),
+    );
+    let digits: Vec<char> = ('0'..='9').collect();
+    add_to_commands(&mut commands, &digits, Box::new(handle_digit_press));
+    Some(Window {
+        ui: Box::new(options_window),
+        commands,
+    })
+}
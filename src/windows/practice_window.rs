@@ -1,51 +1,69 @@
+use crate::add_action_commands;
 use crate::add_to_commands;
+use crate::config::Action;
+use crate::database::load_paragraphs;
 use crate::generate_all_chars;
-use crate::get_app_path;
+use crate::library::{Book, LibraryMarks, RunRules, Selection};
+use crate::theme::Theme;
 use crate::{
-    windows::*, AppParagraph, CharStatus, ParagraphChar, State, Utc, Window, WindowCommand,
+    get_track_record, windows::*, AppParagraph, CharStatus, ParagraphChar, State, Utc, Window,
+    WindowAction, WindowCommand, WindowRender,
 };
-use crossterm::event::KeyCode;
-use rand::prelude::SliceRandom;
-use std::{collections::HashMap, rc::Rc};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::{collections::HashMap, ops::Range, rc::Rc};
 use tui::{
     backend::Backend, layout::Alignment, layout::Constraint, layout::Direction, layout::Layout,
-    style::Color, style::Modifier, style::Style, text::Span, text::Spans, widgets::Block,
-    widgets::Borders, widgets::Gauge, widgets::Paragraph, widgets::Wrap, Frame,
+    layout::Rect, style::Modifier, text::Span, text::Spans, widgets::Block, widgets::Borders,
+    widgets::Gauge, widgets::Paragraph,
 };
 
-pub fn practice_window<B: Backend>(state: Rc<State>) -> Box<dyn Fn(&mut Frame<B>)> {
+const MIN_WIDTH: u16 = 30;
+const MIN_HEIGHT: u16 = 6;
+const MAX_WIDTH: u16 = 90;
+
+pub fn practice_window<B: Backend>(state: Rc<State>) -> WindowRender<B> {
     Box::new(move |f| {
-        let spans: Vec<Span> = state.chars.iter().map(|c| c.to_span()).collect();
+        let theme = &state.config.theme;
+        if render_too_small(f, MIN_WIDTH, MIN_HEIGHT, theme) {
+            return;
+        }
+        let size = f.size();
+        // Long lines are hard to read, wide terminals get a centered column instead.
+        let width = (size.width * 2 / 3)
+            .clamp(MIN_WIDTH, MAX_WIDTH)
+            .min(size.width);
+        let paragraph_height =
+            (state.config.typing.visible_lines as u16).clamp(1, size.height - (MIN_HEIGHT - 1));
+        let container = centered_rect(size, width, paragraph_height + MIN_HEIGHT - 1);
         let layout = Layout::default()
-            .vertical_margin(f.size().height / 5)
-            .horizontal_margin(f.size().width / 3)
             .constraints(
                 [
-                    Constraint::Percentage(50), //Paragraph space
-                    Constraint::Percentage(10), //Live statistics space
-                    Constraint::Percentage(40), //Paragraph information
+                    Constraint::Length(paragraph_height), //Paragraph space
+                    Constraint::Length(1),
+                    Constraint::Length(1), //Live statistics space
+                    Constraint::Length(1),
+                    Constraint::Length(2), //Paragraph information
                 ]
                 .as_ref(),
             )
-            .split(f.size());
+            .split(container);
         let statistics = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-            .split(layout[1]);
+            .split(layout[2]);
         let progress_info = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
                 [
-                    Constraint::Percentage(20), //First player progress bar
-                    Constraint::Percentage(1),
+                    Constraint::Length(2), //First player progress bar
+                    Constraint::Min(0),
                 ]
                 .as_ref(),
             )
-            .split(layout[2]);
+            .split(layout[4]);
 
-        let paragraph = Paragraph::new(vec![Spans::from(spans)])
-            .alignment(Alignment::Center)
-            .wrap(Wrap { trim: false });
+        let lines = visible_paragraph_lines(&state, layout[0], theme);
+        let paragraph = Paragraph::new(lines).alignment(Alignment::Center);
         f.render_widget(paragraph, layout[0]);
 
         let player_statistics = &state.player.statistics;
@@ -53,115 +71,267 @@ pub fn practice_window<B: Backend>(state: Rc<State>) -> Box<dyn Fn(&mut Frame<B>
         let wpm = player_statistics.word_count as f64
             / (time_elapsed.num_milliseconds() as f64 / 1000.0 / 60.0);
         let formatted_wpm = format!("{:.2}", wpm);
-        let wpm_widget = create_label_widget("WPM: ", &formatted_wpm, Color::Yellow);
+        let wpm_widget = create_label_widget("WPM: ", &formatted_wpm, theme.highlight);
         f.render_widget(wpm_widget, statistics[0]);
 
-        let accuracy = (state.chars.len() - player_statistics.total_error_count) as f64
-            / state.chars.len() as f64
+        let paragraph_len = state.paragraph_len();
+        let accuracy = paragraph_len.saturating_sub(player_statistics.total_error_count) as f64
+            / paragraph_len as f64
             * 100.0;
-        let formatted_accuracy = format!("{:.2} %", accuracy);
-        let accuracy_widget = create_label_widget("Accuracy: ", &formatted_accuracy, Color::Yellow);
+        // The accuracy would give the mistakes away.
+        let formatted_accuracy = if state.rules.blind {
+            "Hidden".to_string()
+        } else {
+            format!("{:.2} %", accuracy)
+        };
+        let accuracy_widget =
+            create_label_widget("Accuracy: ", &formatted_accuracy, theme.highlight);
         f.render_widget(accuracy_widget, statistics[1]);
 
-        let progress = state.player.index as f64 / state.chars.len() as f64 * 100.0;
+        let typed_len = state.chars[..state.player.index]
+            .iter()
+            .filter(|c| c.status != CharStatus::Extra)
+            .count();
+        let progress = typed_len as f64 / paragraph_len as f64 * 100.0;
         let progress_widget = Gauge::default()
             .block(
                 Block::default()
                     .borders(Borders::TOP)
                     .title(state.player.user_name.to_string())
-                    .border_style(Style::default().fg(Color::DarkGray)),
-            )
-            .gauge_style(
-                Style::default()
-                    .fg(Color::LightCyan)
-                    .bg(Color::Black)
-                    .add_modifier(Modifier::ITALIC),
+                    .border_style(theme.border),
             )
+            .gauge_style(theme.gauge.add_modifier(Modifier::ITALIC))
             .percent(progress as u16);
         f.render_widget(progress_widget, progress_info[0]);
     })
 }
+/// Word wraps the paragraph to the width of `area` and returns the lines around the one being
+/// typed, so the current line never leaves the screen.
+fn visible_paragraph_lines<'a>(state: &'a State, area: Rect, theme: &Theme) -> Vec<Spans<'a>> {
+    let char_spans: Vec<Vec<Span>> = state
+        .chars
+        .iter()
+        .map(|c| c.to_spans(theme, &state.config.typing, state.rules.blind))
+        .collect();
+    let char_widths: Vec<usize> = char_spans
+        .iter()
+        .map(|spans| spans.iter().map(Span::width).sum())
+        .collect();
+    let line_ranges = wrap_chars(&state.chars, &char_widths, area.width as usize);
+
+    let current_line = line_ranges
+        .iter()
+        .position(|range| range.contains(&state.player.index))
+        .unwrap_or(line_ranges.len().saturating_sub(1));
+    let visible_lines = state
+        .config
+        .typing
+        .visible_lines
+        .clamp(1, std::cmp::max(1, area.height as usize));
+    let first_line = current_line
+        .saturating_sub(visible_lines / 2)
+        .min(line_ranges.len().saturating_sub(visible_lines));
+
+    let mut char_spans = char_spans.into_iter();
+    let mut consumed = 0;
+    line_ranges
+        .iter()
+        .skip(first_line)
+        .take(visible_lines)
+        .map(|range| {
+            let line: Vec<Span> = char_spans
+                .by_ref()
+                .skip(range.start - consumed)
+                .take(range.len())
+                .flatten()
+                .collect();
+            consumed = range.end;
+            Spans::from(line)
+        })
+        .collect()
+}
+
+/// Splits the chars in lines no wider than `width`, breaking lines after spaces. Words that
+/// don't fit in a line by themselves are split.
+fn wrap_chars(chars: &[ParagraphChar], char_widths: &[usize], width: usize) -> Vec<Range<usize>> {
+    let width = std::cmp::max(1, width);
+    let mut lines = vec![];
+    let mut line_start = 0;
+    let mut line_width = 0;
+    let mut word_start = 0;
+    let mut word_width = 0;
+    for (i, c) in chars.iter().enumerate() {
+        word_width += char_widths[i];
+        if word_width > width {
+            // The word alone is too long, break it at this char.
+            if line_start < word_start {
+                lines.push(line_start..word_start);
+            }
+            if word_start < i {
+                lines.push(word_start..i);
+            }
+            line_start = i;
+            word_start = i;
+            line_width = 0;
+            word_width = char_widths[i];
+        }
+        if c.character == ' ' || i == chars.len() - 1 {
+            if line_width + word_width > width {
+                lines.push(line_start..word_start);
+                line_start = word_start;
+                line_width = 0;
+            }
+            line_width += word_width;
+            word_start = i + 1;
+            word_width = 0;
+        }
+    }
+    if line_start < chars.len() {
+        lines.push(line_start..chars.len());
+    }
+    lines
+}
+
+/// Starts practicing a paragraph picked with the practice options, skipping the hidden ones.
 pub fn create_empty_practice_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
-    state.player.reset();
-    state.paragraph = match get_random_app_paragraph() {
-        Ok(p) => p,
+    let paragraph = match get_random_app_paragraph(&state.player.user_name, &mut state.selection)
+    {
+        Ok(Some(paragraph)) => paragraph,
+        Ok(None) => {
+            return create_error_window(
+                "There are no paragraphs to practice\nAdd some to the databases, unhide them in the library or choose other language".to_string(),
+                create_main_menu_window,
+            )
+        }
         Err(err) => {
             return create_error_window(
                 format!(
-                    "Sorry an error ocurred while retrieving the database.csv\n{}",
+                    "The paragraphs couldn't be loaded\n{}",
                     err
                 ),
                 create_main_menu_window,
             )
         }
     };
+    create_paragraph_practice_window(state, paragraph)
+}
+
+/// Starts practicing the given paragraph.
+pub fn create_paragraph_practice_window<B: 'static + Backend>(
+    state: &mut State, paragraph: AppParagraph,
+) -> Option<Window<B>> {
+    state.book = None;
+    state.daily = None;
+    let rules = state.selection.rules();
+    start_practice(state, paragraph, rules)
+}
+
+/// Starts practicing the current paragraph of the book.
+pub fn create_book_practice_window<B: 'static + Backend>(
+    state: &mut State, book: Book,
+) -> Option<Window<B>> {
+    let paragraph = book.current().clone();
+    state.book = Some(book);
+    state.daily = None;
+    let rules = state.selection.rules();
+    start_practice(state, paragraph, rules)
+}
+
+/// Starts the challenge of the day shown in the daily window. Only the first attempt of the day
+/// is scored, later ones are practice.
+pub fn create_daily_practice_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let mut daily = state.daily.take()?;
+    if daily.attempted {
+        daily.scored = false;
+    } else if let Err(err) = daily.start_attempt(&state.player.user_name) {
+        return create_error_window(
+            format!("Sorry, the daily challenge couldn't be started\n{}", err),
+            create_main_menu_window,
+        );
+    }
+    let paragraph = daily.paragraph.clone();
+    state.book = None;
+    state.daily = Some(daily);
+    // Everyone plays the challenge with the same rules.
+    start_practice(state, paragraph, RunRules::default())
+}
+
+fn start_practice<B: 'static + Backend>(
+    state: &mut State, paragraph: AppParagraph, rules: RunRules,
+) -> Option<Window<B>> {
+    state.player.reset();
+    state.rules = rules;
+    state.paragraph = paragraph;
     state.player.statistics.word_count = state.paragraph.get_word_count();
     state.chars = state.paragraph.get_paragraph_chars();
+    if let Some(first_char) = state.chars.first_mut() {
+        first_char.status = CharStatus::Current;
+    }
     create_practice_window(state)
 }
-fn get_random_app_paragraph() -> Result<AppParagraph, csv::Error> {
-    let path = get_app_path("database.csv");
-    csv::Reader::from_path(&path)
-        .and_then(|mut reader| {
-            let mut records: Vec<AppParagraph> = vec![];
-            for result in reader.deserialize() {
-                match result {
-                    Ok(r) => records.push(r),
-                    Err(r) => return Err(r),
-                }
-            }
-            Ok(records)
-        })
-        .and_then(|paragraphs: Vec<AppParagraph>| {
-            let random_par = paragraphs.choose(&mut rand::thread_rng());
-            Ok(random_par
-                .expect("Couldn't get a random paragraph!")
-                .clone())
-        })
+
+fn get_random_app_paragraph(
+    profile: &str, selection: &mut Selection,
+) -> Result<Option<AppParagraph>, String> {
+    let marks = LibraryMarks::load(profile);
+    let paragraphs: Vec<AppParagraph> = load_paragraphs()?
+        .into_iter()
+        .filter(|paragraph| selection.seed.is_some() || !marks.is_hidden(paragraph))
+        .filter(|paragraph| selection.allows(paragraph))
+        .collect();
+    let paragraph = selection.pick(paragraphs, &get_track_record(profile));
+    if selection.seed.is_some() {
+        // Remembers the place in the sequence of the seed.
+        selection
+            .save(profile)
+            .map_err(|err| format!("Couldn't save the options: {}", err))?;
+    }
+    Ok(paragraph)
 }
-fn create_practice_window<B: 'static + Backend>(_: &mut State) -> Option<Window<B>> {
+fn create_practice_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
     fn handle_backspace_press<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
-        if state.player.index != state.chars.len() {
-            state.chars[state.player.index] = ParagraphChar::new(
-                state.chars[state.player.index].character,
-                CharStatus::Default,
-            );
+        if state.player.index == 0 {
+            return create_practice_window(state);
         }
-        if state.player.index > 0 {
-            //Going back to the previous inputted char, because the current is not inputted.
-            state.player.index -= 1;
+        if state.player.index != state.chars.len() {
+            state.chars[state.player.index].status = CharStatus::Default;
         }
-        let current_char = &state.chars[state.player.index];
-        let defaulted_char = match current_char.status {
-            CharStatus::Current => ParagraphChar::new(current_char.character, CharStatus::Current),
-            CharStatus::Correct => ParagraphChar::new(current_char.character, CharStatus::Current),
-            CharStatus::Wrong => {
+        //Going back to the previous inputted char, because the current is not inputted.
+        state.player.index -= 1;
+        match state.chars[state.player.index].status {
+            CharStatus::Wrong => state.player.statistics.current_error_count -= 1,
+            CharStatus::Extra => {
                 state.player.statistics.current_error_count -= 1;
-                ParagraphChar::new(current_char.character, CharStatus::Current)
+                state.chars.remove(state.player.index);
             }
-            CharStatus::Default => ParagraphChar::new(current_char.character, CharStatus::Current),
+            _ => {}
+        }
+        if state.player.index == state.chars.len() {
+            // An extra char typed after the last char of the paragraph was removed.
+            return create_practice_window(state);
+        }
+        let current_char = &state.chars[state.player.index];
+        state.chars[state.player.index] = ParagraphChar {
+            mistyped: current_char.mistyped,
+            ..ParagraphChar::new(current_char.character, CharStatus::Current)
         };
-        state.chars[state.player.index] = defaulted_char;
         create_practice_window(state)
     }
 
-    let mut commands = HashMap::from([
-        (
-            KeyCode::Esc,
-            WindowCommand {
-                activator_key: KeyCode::Esc,
-                action: Box::new(create_main_menu_window),
-            },
-        ),
-        (
-            KeyCode::Backspace,
-            WindowCommand {
-                activator_key: KeyCode::Backspace,
-                action: Box::new(handle_backspace_press),
-            },
-        ),
-    ]);
+    let mut commands = HashMap::new();
+    add_action_commands(
+        &mut commands,
+        state.config.key_bindings.keys(Action::Back),
+        create_main_menu_window,
+    );
+    if !state.rules.no_backspace {
+        commands.insert(
+            KeyEvent::from(KeyCode::Backspace),
+            WindowCommand::new_key_command(KeyCode::Backspace, Box::new(handle_backspace_press)),
+        );
+    }
 
+    // Typing always wins over the bindings, a paragraph must be typeable.
     let chars = generate_all_chars();
     add_to_commands(&mut commands, &chars, Box::new(handle_char_press));
     Some(Window {
@@ -170,20 +340,43 @@ fn create_practice_window<B: 'static + Backend>(_: &mut State) -> Option<Window<
     })
 }
 
-fn handle_char_press<B: 'static + Backend>(
-    pressed_character: char,
-) -> Box<dyn Fn(&mut State) -> Option<Window<B>>> {
+fn handle_char_press<B: 'static + Backend>(pressed_character: char) -> WindowAction<B> {
     Box::new(move |state: &mut State| {
-        let current_char = &state.chars[state.player.index];
-        let is_correct = current_char.character == pressed_character;
-        let status = if is_correct {
-            CharStatus::Correct
-        } else {
-            CharStatus::Wrong
-        };
+        let show_extra_chars = state.config.typing.show_extra_chars;
+        if state.player.index == state.chars.len() && !show_extra_chars {
+            // The paragraph was typed but still has errors, there's no char left to type.
+            return create_practice_window(state);
+        }
+        let current_char = state.chars.get(state.player.index);
+        let is_correct = current_char.is_some_and(|c| c.character == pressed_character);
+        let is_extra =
+            !is_correct && show_extra_chars && current_char.is_none_or(|c| c.character == ' ');
 
-        let transformed_char = ParagraphChar::new(current_char.character, status);
-        state.chars[state.player.index] = transformed_char;
+        if is_extra {
+            let extra_char =
+                ParagraphChar::new_typed(pressed_character, pressed_character, CharStatus::Extra);
+            match state.chars.get_mut(state.player.index) {
+                Some(space) => {
+                    // The space was missed, it's the char counted as mistyped.
+                    space.mistyped = true;
+                    state.chars.insert(state.player.index, extra_char);
+                }
+                // Typed after the last char of the paragraph.
+                None => state.chars.push(extra_char),
+            }
+        } else {
+            let current_char = &state.chars[state.player.index];
+            let status = if is_correct {
+                CharStatus::Correct
+            } else {
+                CharStatus::Wrong
+            };
+            state.chars[state.player.index] = ParagraphChar {
+                mistyped: current_char.mistyped || !is_correct,
+                ..ParagraphChar::new_typed(current_char.character, pressed_character, status)
+            };
+        }
+        state.player.statistics.add_keystroke(is_correct);
 
         state.player.index += 1;
 
@@ -193,16 +386,23 @@ fn handle_char_press<B: 'static + Backend>(
         }
 
         let end_of_paragraph = state.player.index == state.chars.len();
+        let failed = state.rules.fails(state.player.statistics.total_error_count);
+        // Without backspace the errors can't be fixed, and blind runs don't show them, so the
+        // run ends with them.
+        let fixed = state.player.statistics.current_error_count == 0
+            || state.rules.no_backspace
+            || state.rules.blind;
 
-        if end_of_paragraph && state.player.statistics.current_error_count == 0 {
+        if failed {
+            state.player.statistics.failed = true;
+            state.player.statistics.end_time = Utc::now();
+            create_end_window(state)
+        } else if end_of_paragraph && fixed {
             state.player.statistics.end_time = Utc::now();
             create_end_window(state)
         } else {
             if !end_of_paragraph {
-                let current_char = &state.chars[state.player.index];
-                let transformed_char =
-                    ParagraphChar::new(current_char.character, CharStatus::Current);
-                state.chars[state.player.index] = transformed_char;
+                state.chars[state.player.index].status = CharStatus::Current;
             }
             create_practice_window(state)
         }
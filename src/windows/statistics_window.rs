@@ -1,16 +1,26 @@
+use crate::config::key_to_string;
+use crate::difficulty::Difficulty;
+use crate::export::{export_runs, runs_since, ExportFormat};
 use crate::get_track_record;
+use crate::theme::Theme;
 use crate::windows::*;
 use crate::State;
+use crate::StatisticsState;
+use crate::TraceRun;
 use crate::Window;
-use crate::WindowCommand;
-use crossterm::event::KeyCode;
+use crate::WindowRender;
+use crate::{add_action_commands, config::Action, get_app_path, language_label, source_label};
+use crate::{RunAverage, RunSummary, RUNS_PER_DAY_DAYS};
+use chrono::{Local, Utc};
 use std::collections::HashMap;
+use std::ops::Range;
 use std::rc::Rc;
 use tui::backend::Backend;
 use tui::layout::Alignment;
 use tui::layout::Constraint;
 use tui::layout::Direction;
 use tui::layout::Layout;
+use tui::layout::Rect;
 use tui::symbols;
 use tui::widgets::Axis;
 use tui::widgets::BarChart;
@@ -18,98 +28,174 @@ use tui::widgets::Block;
 use tui::widgets::Chart;
 use tui::widgets::Dataset;
 use tui::widgets::GraphType;
+use tui::widgets::Paragraph;
 use tui::Frame;
 
-fn construct_line_chart<B: Backend>(
-    f: &mut Frame<B>, point_series: &[(f64, f64)], wpm_series: &[(f64, f64)],
-    accuracy_series: &[(f64, f64)], raw_data_length: usize,
-) {
-    let datasets = vec![
-        Dataset::default()
-            .name("Points")
-            .marker(symbols::Marker::Braille)
-            .graph_type(GraphType::Line)
-            .style(Style::default().fg(Color::LightCyan))
-            .data(point_series),
-        Dataset::default()
-            .name("WPM")
-            .marker(symbols::Marker::Braille)
-            .graph_type(GraphType::Line)
-            .style(Style::default().fg(Color::LightYellow))
-            .data(wpm_series),
-        Dataset::default()
-            .name("Accuracy")
-            .marker(symbols::Marker::Braille)
-            .graph_type(GraphType::Line)
-            .style(Style::default().fg(Color::LightGreen))
-            .data(&accuracy_series),
-    ];
+/// Runs averaged by the trend lines.
+const MOVING_AVERAGE_RUNS: usize = 10;
 
-    let filter = |n: usize| {
-        let potential_step = (0.1 * raw_data_length as f64) as usize;
-        let step = if potential_step == 0 {
-            1
-        } else {
-            potential_step
+/// Points, WPM and accuracy of the runs of a page, keyed by the number of the run in the history.
+struct RunSeries {
+    points: Vec<(f64, f64)>,
+    wpm: Vec<(f64, f64)>,
+    accuracy: Vec<(f64, f64)>,
+    points_trend: Vec<(f64, f64)>,
+    wpm_trend: Vec<(f64, f64)>,
+    accuracy_trend: Vec<(f64, f64)>,
+}
+
+impl RunSeries {
+    /// `page` are the positions inside `indexes` to keep. The trends are computed with the runs
+    /// before the page too, so they don't restart on every page.
+    fn new(runs: &[TraceRun], indexes: &[usize], page: Range<usize>) -> RunSeries {
+        let series = |value: fn(&TraceRun) -> f64| -> Vec<(f64, f64)> {
+            indexes[page.clone()]
+                .iter()
+                .map(|i| (*i as f64, value(&runs[*i])))
+                .collect()
         };
-        if n % step == 0 {
-            Some(Span::from(n.to_string()))
-        } else {
-            None
+        let trend = |value: fn(&TraceRun) -> f64| -> Vec<(f64, f64)> {
+            page.clone()
+                .map(|position| {
+                    let window_start = (position + 1).saturating_sub(MOVING_AVERAGE_RUNS);
+                    let window = &indexes[window_start..=position];
+                    let sum: f64 = window.iter().map(|i| value(&runs[*i])).sum();
+                    (indexes[position] as f64, sum / window.len() as f64)
+                })
+                .collect()
+        };
+        RunSeries {
+            points: series(|run| run.total_points),
+            wpm: series(|run| run.wpm),
+            accuracy: series(|run| run.accuracy * 100.0),
+            points_trend: trend(|run| run.total_points),
+            wpm_trend: trend(|run| run.wpm),
+            accuracy_trend: trend(|run| run.accuracy * 100.0),
         }
-    };
+    }
+}
+
+fn construct_line_chart<B: Backend>(
+    f: &mut Frame<B>, area: Rect, series: &RunSeries, selected: Option<usize>, title: &str,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .title(title)
+        .title_alignment(Alignment::Center);
+    let inner_area = block.inner(area);
+    f.render_widget(block, area);
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+        .split(inner_area);
 
-    let max_bound = std::cmp::max(1, raw_data_length - 1);
-    let labels = if raw_data_length == 1 {
-        vec![Span::from("0"), Span::from("1")]
+    // The x axis uses the number of the run in the whole history.
+    let first_run = series.points.first().map_or(0.0, |p| p.0);
+    let last_run = series.points.last().map_or(0.0, |p| p.0);
+    let max_bound = if last_run > first_run {
+        last_run
     } else {
-        (0..raw_data_length).filter_map(filter).collect()
+        first_run + 1.0
     };
-    let chart = Chart::new(datasets)
-        .block(
-            Block::default()
-                .title("Statistics")
-                .title_alignment(Alignment::Center),
-        )
-        .x_axis(
-            Axis::default()
-                .title(Span::styled(
-                    "# Run",
-                    Style::default()
-                        .fg(Color::LightRed)
-                        .add_modifier(Modifier::BOLD),
-                ))
-                .bounds([0.0, max_bound as f64])
-                .labels(labels),
-        )
-        .y_axis(
-            Axis::default()
-                .title(Span::styled(
-                    "Press [TAB] to change",
-                    Style::default()
-                        .fg(Color::LightRed)
-                        .add_modifier(Modifier::BOLD),
-                ))
-                .bounds([0.0, 150.0])
-                .labels(
-                    (0..151)
-                        .filter_map(|n: u8| {
-                            if n % 10 == 0 {
-                                Some(Span::from(n.to_string()))
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
-                ),
-        );
-    f.render_widget(chart, f.size());
+    let label_count = std::cmp::min(5, (max_bound - first_run) as usize + 1);
+    let x_axis = || {
+        Axis::default()
+            .title(Span::styled(
+                "# Run",
+                theme.axis.add_modifier(Modifier::BOLD),
+            ))
+            .bounds([first_run, max_bound])
+            .labels(axis_labels([first_run, max_bound], label_count))
+    };
+    let selected_point = |data: &[(f64, f64)]| -> Vec<(f64, f64)> {
+        data.iter()
+            .filter(|p| Some(p.0 as usize) == selected)
+            .copied()
+            .collect()
+    };
+    let trend_style = |style: Style| style.add_modifier(Modifier::BOLD);
+    let raw_style = |style: Style| style.add_modifier(Modifier::DIM);
+
+    let points_trend_name = format!("Points (avg. {})", MOVING_AVERAGE_RUNS);
+    let wpm_trend_name = format!("WPM (avg. {})", MOVING_AVERAGE_RUNS);
+    let accuracy_trend_name = format!("Accuracy (avg. {})", MOVING_AVERAGE_RUNS);
+    let mut selected_speed = selected_point(&series.points);
+    selected_speed.append(&mut selected_point(&series.wpm));
+    let speed_bounds = value_bounds(
+        &[
+            &series.points,
+            &series.wpm,
+            &series.points_trend,
+            &series.wpm_trend,
+        ],
+        0.0,
+        f64::MAX,
+    );
+    let speed_chart = Chart::new(vec![
+        line_dataset("Points", &series.points, raw_style(theme.points)),
+        line_dataset("WPM", &series.wpm, raw_style(theme.wpm)),
+        line_dataset(
+            &points_trend_name,
+            &series.points_trend,
+            trend_style(theme.points),
+        ),
+        line_dataset(&wpm_trend_name, &series.wpm_trend, trend_style(theme.wpm)),
+        Dataset::default()
+            .marker(symbols::Marker::Block)
+            .graph_type(GraphType::Scatter)
+            .style(theme.highlight)
+            .data(&selected_speed),
+    ])
+    .x_axis(x_axis())
+    .y_axis(
+        Axis::default()
+            .title(Span::styled(
+                "Points / WPM",
+                theme.axis.add_modifier(Modifier::BOLD),
+            ))
+            .bounds(speed_bounds)
+            .labels(axis_labels(speed_bounds, 5)),
+    );
+    f.render_widget(speed_chart, layout[0]);
+
+    let selected_accuracy = selected_point(&series.accuracy);
+    let accuracy_bounds = value_bounds(&[&series.accuracy, &series.accuracy_trend], 0.0, 100.0);
+    let accuracy_chart = Chart::new(vec![
+        line_dataset("Accuracy", &series.accuracy, raw_style(theme.accuracy)),
+        line_dataset(
+            &accuracy_trend_name,
+            &series.accuracy_trend,
+            trend_style(theme.accuracy),
+        ),
+        Dataset::default()
+            .marker(symbols::Marker::Block)
+            .graph_type(GraphType::Scatter)
+            .style(theme.highlight)
+            .data(&selected_accuracy),
+    ])
+    .x_axis(x_axis())
+    .y_axis(
+        Axis::default()
+            .title(Span::styled(
+                "Accuracy %",
+                theme.axis.add_modifier(Modifier::BOLD),
+            ))
+            .bounds(accuracy_bounds)
+            .labels(axis_labels(accuracy_bounds, 3)),
+    );
+    f.render_widget(accuracy_chart, layout[1]);
 }
 
 fn construct_bar_charts<B: Backend>(
-    f: &mut Frame<B>, point_series: &[(f64, f64)], wpm_series: &[(f64, f64)],
-    accuracy_series: &[(f64, f64)],
+    f: &mut Frame<B>, area: Rect, series: &RunSeries, selected: Option<f64>, title: &str,
+    theme: &Theme,
 ) {
+    let block = Block::default()
+        .title(title)
+        .title_alignment(Alignment::Center);
+    let inner_area = block.inner(area);
+    f.render_widget(block, area);
+
     let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -120,16 +206,11 @@ fn construct_bar_charts<B: Backend>(
             ]
             .as_ref(),
         )
-        .split(f.size());
+        .split(inner_area);
 
-    let series = [point_series, wpm_series, accuracy_series];
+    let series = [&series.points, &series.wpm, &series.accuracy];
     let titles = ["Points", "WPM", "Accuracy"];
-    let colors = [Color::LightCyan, Color::LightYellow, Color::LightGreen];
-    let bar_styles = [
-        Style::default().fg(Color::LightCyan),
-        Style::default().fg(Color::LightYellow),
-        Style::default().fg(Color::LightGreen),
-    ];
+    let bar_styles = [theme.points, theme.wpm, theme.accuracy];
     let value_styles = [
         Style::default().add_modifier(Modifier::BOLD),
         Style::default().add_modifier(Modifier::BOLD),
@@ -137,8 +218,15 @@ fn construct_bar_charts<B: Backend>(
     ];
     for i in 0..3 {
         let transformed_series: Vec<(String, u64)> = series[i]
-            .into_iter()
-            .map(|v| (v.0.to_string(), v.1 as u64))
+            .iter()
+            .map(|v| {
+                let label = if Some(v.0) == selected {
+                    "►".to_string()
+                } else {
+                    v.0.to_string()
+                };
+                (label, v.1 as u64)
+            })
             .rev()
             .collect();
         let result: Vec<(&str, u64)> = transformed_series
@@ -150,7 +238,7 @@ fn construct_bar_charts<B: Backend>(
                 Block::default()
                     .title(Span::styled(
                         titles[i],
-                        Style::default().fg(colors[i]).add_modifier(Modifier::BOLD),
+                        bar_styles[i].add_modifier(Modifier::BOLD),
                     ))
                     .borders(Borders::BOTTOM),
             )
@@ -158,61 +246,697 @@ fn construct_bar_charts<B: Backend>(
             .bar_gap(1)
             .bar_style(bar_styles[i])
             .value_style(value_styles[i])
-            .label_style(Style::default().fg(Color::White))
+            .label_style(theme.chart_label)
             .data(&result);
         f.render_widget(chart, layout[i]);
     }
 }
 
-fn statistics_window<B: 'static + Backend>(state: Rc<State>) -> Box<dyn Fn(&mut Frame<B>)> {
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    format!(
+        "{}h {:02}m {:02}s",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn best_run_line<'a>(
+    label: &'a str, runs: &[TraceRun], best: Option<usize>, value: fn(&TraceRun) -> String,
+    style: Style, theme: &Theme,
+) -> Spans<'a> {
+    let best = match best {
+        Some(i) => &runs[i],
+        None => return Spans::from(Span::styled(format!("{:<12}-", label), theme.border)),
+    };
+    let title = if best.title.is_empty() {
+        "Unknown paragraph"
+    } else {
+        best.title.as_str()
+    };
+    Spans::from(vec![
+        Span::styled(format!("{:<12}", label), theme.text),
+        Span::styled(format!("{:>10}", value(best)), style),
+        Span::styled(format!("  {}", title), theme.info),
+    ])
+}
+
+fn average_line<'a>(
+    label: &'a str, averages: [Option<RunAverage>; 2], value: fn(&RunAverage) -> String,
+    style: Style, theme: &Theme,
+) -> Spans<'a> {
+    let mut spans = vec![Span::styled(format!("{:<12}", label), theme.text)];
+    for average in averages {
+        let value = average.as_ref().map_or("-".to_string(), value);
+        spans.push(Span::styled(format!("{:>10}", value), style));
+    }
+    Spans::from(spans)
+}
+
+fn construct_dashboard<B: Backend>(
+    f: &mut Frame<B>, area: Rect, runs: &[TraceRun], summary: &RunSummary, title: &str,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .title(title)
+        .title_alignment(Alignment::Center);
+    let inner_area = block.inner(area);
+    f.render_widget(block, area);
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(14), Constraint::Min(0)].as_ref())
+        .split(inner_area);
+
+    let section = |name: &'static str| {
+        Spans::from(Span::styled(
+            name,
+            theme.highlight.add_modifier(Modifier::BOLD),
+        ))
+    };
+    let averages = [summary.last_10, summary.last_100];
+    let lines = vec![
+        section("Personal bests"),
+        best_run_line(
+            "WPM",
+            runs,
+            summary.best_wpm,
+            |run| format!("{:.2}", run.wpm),
+            theme.wpm,
+            theme,
+        ),
+        best_run_line(
+            "Accuracy",
+            runs,
+            summary.best_accuracy,
+            |run| format!("{:.2} %", run.accuracy * 100.0),
+            theme.accuracy,
+            theme,
+        ),
+        best_run_line(
+            "Points",
+            runs,
+            summary.best_points,
+            |run| format!("{:.2}", run.total_points),
+            theme.points,
+            theme,
+        ),
+        Spans::from(""),
+        Spans::from(vec![
+            Span::styled(
+                format!("{:<12}", "Averages"),
+                theme.highlight.add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("{:>10}{:>10}", "Last 10", "Last 100"), theme.border),
+        ]),
+        average_line(
+            "WPM",
+            averages,
+            |average| format!("{:.2}", average.wpm),
+            theme.wpm,
+            theme,
+        ),
+        average_line(
+            "Accuracy",
+            averages,
+            |average| format!("{:.2} %", average.accuracy * 100.0),
+            theme.accuracy,
+            theme,
+        ),
+        average_line(
+            "Points",
+            averages,
+            |average| format!("{:.2}", average.total_points),
+            theme.points,
+            theme,
+        ),
+        Spans::from(""),
+        section("Totals"),
+        Spans::from(vec![
+            Span::styled(format!("{:<12}", "Runs"), theme.text),
+            Span::styled(format!("{:>10}", summary.run_count), theme.info),
+            Span::styled("   Time typed ", theme.text),
+            Span::styled(format_duration(summary.total_seconds), theme.info),
+            Span::styled("   Characters ", theme.text),
+            Span::styled(summary.total_characters.to_string(), theme.info),
+        ]),
+        Spans::from(vec![
+            Span::styled(format!("{:<12}", "Runs / day"), theme.text),
+            Span::styled(format!("{:>10.2}", summary.runs_per_active_day), theme.info),
+            Span::styled("   Current streak ", theme.text),
+            Span::styled(
+                format!(
+                    "{} day{}",
+                    summary.streak,
+                    if summary.streak == 1 { "" } else { "s" }
+                ),
+                theme.highlight,
+            ),
+            Span::styled("   Failed runs ", theme.text),
+            Span::styled(summary.failed_runs.to_string(), theme.info),
+        ]),
+    ];
+    let summary_area = centered_rect(layout[0], 70, layout[0].height);
+    f.render_widget(Paragraph::new(lines), summary_area);
+
+    let labels: Vec<String> = summary
+        .runs_per_day
+        .iter()
+        .map(|(day, _)| day.format("%d").to_string())
+        .collect();
+    let data: Vec<(&str, u64)> = summary
+        .runs_per_day
+        .iter()
+        .zip(&labels)
+        .map(|((_, count), label)| (label.as_str(), *count as u64))
+        .collect();
+    let chart = BarChart::default()
+        .block(Block::default().title(Span::styled(
+            format!("Runs per day (last {} days)", RUNS_PER_DAY_DAYS),
+            theme.highlight.add_modifier(Modifier::BOLD),
+        )))
+        .bar_width(3)
+        .bar_gap(1)
+        .bar_style(theme.points)
+        .value_style(Style::default().add_modifier(Modifier::BOLD))
+        .label_style(theme.chart_label)
+        .data(&data);
+    f.render_widget(chart, centered_rect(layout[1], 70, layout[1].height));
+}
+
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 12;
+/// Rows of the timeline of the selected run.
+const TIMELINE_HEIGHT: u16 = 10;
+
+const PAGE_SIZE: usize = 30;
+
+/// Positions, inside the runs of the range, of the runs shown in `page`.
+fn page_bounds(run_count: usize, page: usize) -> Range<usize> {
+    let end = run_count.saturating_sub(page * PAGE_SIZE);
+    end.saturating_sub(PAGE_SIZE)..end
+}
+
+fn page_count(run_count: usize) -> usize {
+    std::cmp::max(1, run_count.div_ceil(PAGE_SIZE))
+}
+
+/// Runs shown with the filters of the window. Failed runs are left out of the charts and
+/// averages, they're only counted.
+fn range_runs(statistics: &StatisticsState) -> Vec<usize> {
+    passed_runs(&statistics.runs, &selected_runs(statistics))
+}
+
+/// The runs of `selected` that didn't fail, the ones in the charts and the table.
+fn passed_runs(runs: &[TraceRun], selected: &[usize]) -> Vec<usize> {
+    selected
+        .iter()
+        .copied()
+        .filter(|i| !runs[*i].failed)
+        .collect()
+}
+
+/// Runs that pass the filters of the window, failed ones included, cut to the range in the order
+/// of the history. The range is applied once, so "Last 10 runs" are the last 10 runs played, whether
+/// they failed or not.
+fn selected_runs(statistics: &StatisticsState) -> Vec<usize> {
+    let runs = &statistics.runs;
+    let filtered_runs: Vec<usize> = (0..runs.len())
+        .filter(|i| {
+            statistics
+                .source
+                .as_ref()
+                .is_none_or(|source| runs[*i].source == *source)
+        })
+        .filter(|i| {
+            statistics
+                .language
+                .as_ref()
+                .is_none_or(|language| runs[*i].language == *language)
+        })
+        .filter(|i| {
+            statistics.difficulty.is_none_or(|difficulty| {
+                runs[*i]
+                    .difficulty
+                    .is_some_and(|score| Difficulty::from_score(score) == difficulty)
+            })
+        })
+        .collect();
+    statistics.range.filter(runs, &filtered_runs, Utc::now())
+}
+
+/// Values of a field of the runs of the history, like their sources, sorted. Empty values, like
+/// the source of the runs played here, go first.
+fn run_values(runs: &[TraceRun], field: fn(&TraceRun) -> &String) -> Vec<String> {
+    let mut values: Vec<String> = runs.iter().map(|run| field(run).clone()).collect();
+    values.sort();
+    values.dedup();
+    values
+}
+
+/// Goes from every value to each of `values`, and back.
+fn next_value(values: &[String], current: &Option<String>) -> Option<String> {
+    let next = match current {
+        None => 0,
+        Some(current) => values
+            .iter()
+            .position(|value| value == current)
+            .map_or(0, |i| i + 1),
+    };
+    values.get(next).cloned()
+}
+
+/// Label of the range, source and language of the runs shown.
+fn filter_label(statistics: &StatisticsState) -> String {
+    let mut label = statistics.range.label().to_string();
+    if let Some(source) = &statistics.source {
+        label.push_str(&format!(" · {}", source_label(source)));
+    }
+    if let Some(language) = &statistics.language {
+        label.push_str(&format!(" · {}", language_label(language)));
+    }
+    if let Some(difficulty) = statistics.difficulty {
+        label.push_str(&format!(" · {}", difficulty.label()));
+    }
+    label
+}
+
+fn statistics_window<B: 'static + Backend>(state: Rc<State>) -> WindowRender<B> {
     Box::new(move |f: &mut Frame<B>| {
-        let raw_data = get_track_record();
+        let theme = &state.config.theme;
+        if render_too_small(f, MIN_WIDTH, MIN_HEIGHT, theme) {
+            return;
+        }
+        let statistics = &state.statistics;
+        // The table and the dashboard are cut from the same runs.
+        let selected_runs = selected_runs(statistics);
+        let range_runs = passed_runs(&statistics.runs, &selected_runs);
 
-        let point_series: Vec<(f64, f64)> = (0..raw_data.len())
-            .map(|i| (i as f64, raw_data[i].total_points))
-            .collect();
-        let wpm_series: Vec<(f64, f64)> = (0..raw_data.len())
-            .map(|i| (i as f64, raw_data[i].wpm))
-            .collect();
-        let accuracy_series: Vec<(f64, f64)> = (0..raw_data.len())
-            .map(|i| (i as f64, raw_data[i].accuracy * 100.0))
-            .collect();
+        let page = page_bounds(range_runs.len(), statistics.page);
+        let series = RunSeries::new(&statistics.runs, &range_runs, page);
+
+        let selected_run = statistics.cursor.map(|cursor| range_runs[cursor]);
+        let title = format!(
+            "Statistics · {} · Page {}/{}",
+            filter_label(statistics),
+            statistics.page + 1,
+            page_count(range_runs.len())
+        );
 
-        if state.show_bar_charts {
-            construct_bar_charts(f, &point_series, &wpm_series, &accuracy_series);
+        // The timeline of the selected run goes under the charts.
+        let timeline_run = selected_run
+            .map(|i| &statistics.runs[i])
+            .filter(|run| !run.timeline.is_empty() && !statistics.dashboard);
+        let timeline_height = if timeline_run.is_some() {
+            std::cmp::min(TIMELINE_HEIGHT, f.size().height / 3)
         } else {
-            construct_line_chart(
+            0
+        };
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Min(0),
+                    Constraint::Length(timeline_height),
+                    Constraint::Length(2),
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
+        if let (Some(run), Some(i)) = (timeline_run, selected_run) {
+            let title = format!("Run #{} · WPM per second", i);
+            render_timeline(f, layout[1], run, &title, theme);
+        }
+
+        if statistics.dashboard {
+            let summary = RunSummary::new(
+                &statistics.runs,
+                &selected_runs,
+                Local::now().naive_local().date(),
+            );
+            let title = format!("Statistics · {} · Dashboard", filter_label(statistics));
+            construct_dashboard(f, layout[0], &statistics.runs, &summary, &title, theme);
+        } else if state.show_bar_charts {
+            construct_bar_charts(
                 f,
-                &point_series,
-                &wpm_series,
-                &accuracy_series,
-                raw_data.len(),
+                layout[0],
+                &series,
+                selected_run.map(|i| i as f64),
+                &title,
+                theme,
             );
+        } else {
+            construct_line_chart(f, layout[0], &series, selected_run, &title, theme);
         }
+
+        f.render_widget(run_details(&state, selected_run), layout[2]);
     })
 }
 
-pub fn create_statistics_window<B: 'static + Backend>(_: &mut State) -> Option<Window<B>> {
+fn run_details(state: &State, selected_run: Option<usize>) -> Paragraph<'_> {
+    let theme = &state.config.theme;
+    let key_bindings = &state.config.key_bindings;
+    let key = |action: Action| {
+        key_bindings
+            .keys(action)
+            .first()
+            .map(|key| key_to_string(*key))
+            .unwrap_or_default()
+    };
+    let details = match selected_run {
+        Some(i) => {
+            let run = &state.statistics.runs[i];
+            let date = run
+                .date
+                .map(|date| {
+                    date.with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_else(|| "Unknown date".to_string());
+            Spans::from(vec![
+                Span::styled(
+                    format!("Run #{}", i),
+                    theme.highlight.add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!(" · {}", date), theme.text),
+                Span::styled(format!(" · {:.2} WPM", run.wpm), theme.wpm),
+                Span::styled(format!(" · {:.2} %", run.accuracy * 100.0), theme.accuracy),
+                Span::styled(format!(" · {:.2} points", run.total_points), theme.points),
+                Span::styled(format!(" · {:.2} s", run.seconds), theme.text),
+                Span::styled(
+                    run.difficulty
+                        .map(|score| {
+                            format!(
+                                " · {} ({:.0})",
+                                Difficulty::from_score(score).label(),
+                                score
+                            )
+                        })
+                        .unwrap_or_default(),
+                    theme.text,
+                ),
+                Span::styled(
+                    if run.mode.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" · {}", run.mode)
+                    },
+                    theme.text,
+                ),
+            ])
+        }
+        None => Spans::from(Span::styled(
+            "Select a run to see its details",
+            theme.border,
+        )),
+    };
+    let help = Spans::from(Span::styled(
+        format!(
+            "[{}/{}] Page  [{}/{}] Run  [{}] Range  [{}] Source  [{}] Language  [{}] Difficulty  [{}] Chart  [{}] Dashboard  [{}] Export  [{}] Back",
+            key(Action::PreviousPage),
+            key(Action::NextPage),
+            key(Action::PreviousRun),
+            key(Action::NextRun),
+            key(Action::CycleRange),
+            key(Action::CycleSource),
+            key(Action::Language),
+            key(Action::CycleDifficulty),
+            key(Action::ToggleChart),
+            key(Action::Dashboard),
+            key(Action::Export),
+            key(Action::Back),
+        ),
+        theme.border,
+    ));
+    Paragraph::new(vec![details, help]).alignment(Alignment::Center)
+}
+
+/// Loads the run history and opens the statistics window on its most recent page.
+pub fn create_statistics_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    state.statistics.runs = get_track_record(&state.player.user_name);
+    state.statistics.page = 0;
+    state.statistics.cursor = None;
+    create_loaded_statistics_window(state)
+}
+
+fn create_loaded_statistics_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let key_bindings = &state.config.key_bindings;
+    let mut commands = HashMap::new();
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Back),
+        create_main_menu_window,
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::ToggleChart),
+        |s: &mut State| {
+            s.show_bar_charts = !s.show_bar_charts;
+            s.statistics.dashboard = false;
+            create_loaded_statistics_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Export),
+        |s: &mut State| {
+            s.statistics.export_message = None;
+            create_export_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Dashboard),
+        |s: &mut State| {
+            s.statistics.dashboard = !s.statistics.dashboard;
+            create_loaded_statistics_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::PreviousPage),
+        |s: &mut State| {
+            let run_count = range_runs(&s.statistics).len();
+            s.statistics.page = std::cmp::min(s.statistics.page + 1, page_count(run_count) - 1);
+            s.statistics.cursor = None;
+            create_loaded_statistics_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::NextPage),
+        |s: &mut State| {
+            s.statistics.page = s.statistics.page.saturating_sub(1);
+            s.statistics.cursor = None;
+            create_loaded_statistics_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::PreviousRun),
+        |s: &mut State| {
+            let run_count = range_runs(&s.statistics).len();
+            let page = page_bounds(run_count, s.statistics.page);
+            if !page.is_empty() {
+                let cursor = match s.statistics.cursor {
+                    Some(cursor) => cursor.saturating_sub(1),
+                    None => page.end - 1,
+                };
+                if cursor < page.start {
+                    s.statistics.page += 1;
+                }
+                s.statistics.cursor = Some(cursor);
+            }
+            create_loaded_statistics_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::NextRun),
+        |s: &mut State| {
+            let run_count = range_runs(&s.statistics).len();
+            let page = page_bounds(run_count, s.statistics.page);
+            if !page.is_empty() {
+                let cursor = match s.statistics.cursor {
+                    Some(cursor) => std::cmp::min(cursor + 1, run_count - 1),
+                    None => page.start,
+                };
+                if cursor >= page.end {
+                    s.statistics.page -= 1;
+                }
+                s.statistics.cursor = Some(cursor);
+            }
+            create_loaded_statistics_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::CycleRange),
+        |s: &mut State| {
+            s.statistics.range = s.statistics.range.next();
+            s.statistics.page = 0;
+            s.statistics.cursor = None;
+            create_loaded_statistics_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::CycleSource),
+        |s: &mut State| {
+            let sources = run_values(&s.statistics.runs, |run| &run.source);
+            s.statistics.source = next_value(&sources, &s.statistics.source);
+            s.statistics.page = 0;
+            s.statistics.cursor = None;
+            create_loaded_statistics_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::Language),
+        |s: &mut State| {
+            let languages = run_values(&s.statistics.runs, |run| &run.language);
+            s.statistics.language = next_value(&languages, &s.statistics.language);
+            s.statistics.page = 0;
+            s.statistics.cursor = None;
+            create_loaded_statistics_window(s)
+        },
+    );
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::CycleDifficulty),
+        |s: &mut State| {
+            s.statistics.difficulty = match s.statistics.difficulty {
+                None => Some(Difficulty::Easy),
+                Some(Difficulty::Easy) => Some(Difficulty::Medium),
+                Some(Difficulty::Medium) => Some(Difficulty::Hard),
+                Some(Difficulty::Hard) => None,
+            };
+            s.statistics.page = 0;
+            s.statistics.cursor = None;
+            create_loaded_statistics_window(s)
+        },
+    );
     Some(Window {
         ui: Box::new(statistics_window),
-        commands: HashMap::from([
-            (
-                KeyCode::Esc,
-                WindowCommand {
-                    activator_key: KeyCode::Esc,
-                    action: Box::new(create_main_menu_window),
-                },
-            ),
-            (
-                KeyCode::Tab,
-                WindowCommand {
-                    activator_key: KeyCode::Tab,
-                    action: Box::new(|s: &mut State| {
-                        s.show_bar_charts = !s.show_bar_charts;
-                        create_statistics_window(s)
-                    }),
-                },
-            ),
-        ]),
+        commands,
+    })
+}
+
+/// Action and button label of every export format.
+const EXPORT_FORMATS: [(Action, &str); 3] = [
+    (Action::ExportJson, "Json"),
+    (Action::ExportCsv, "Csv"),
+    (Action::ExportMarkdown, "Markdown"),
+];
+
+fn export_window<B: Backend>(state: Rc<State>) -> WindowRender<B> {
+    Box::new(move |f| {
+        let title = format!(
+            "Export the run history ({} runs)\n{}",
+            state.statistics.runs.len(),
+            state.statistics.export_message.as_deref().unwrap_or(""),
+        );
+        let key_bindings = &state.config.key_bindings;
+        let mut labels: Vec<(String, String)> = EXPORT_FORMATS
+            .iter()
+            .map(|(action, label)| key_bindings.button_label(*action, label))
+            .collect();
+        labels.push(key_bindings.button_label(Action::Back, "Back"));
+        let buttons = labels
+            .iter()
+            .map(|(activator, rest)| (activator.as_str(), rest.as_str()))
+            .collect();
+        create_menu(f, &title, buttons, &state.config.theme);
     })
 }
+
+/// Writes the whole run history next to the database and tells where in the menu. The runs are
+/// picked like in `stats export`, so both give the same file.
+fn export_statistics<B: 'static + Backend>(
+    state: &mut State, format: ExportFormat,
+) -> Option<Window<B>> {
+    let file_name = format!(
+        "trace-export-{}.{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    );
+    let path = get_app_path(&file_name);
+    let indexes = runs_since(&state.statistics.runs, None);
+    let result = std::fs::File::create(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|file| export_runs(&state.statistics.runs, &indexes, format, file));
+    state.statistics.export_message = Some(match result {
+        Ok(()) => format!("Exported to {}", file_name),
+        Err(err) => format!("Couldn't export: {}", err),
+    });
+    create_export_window(state)
+}
+
+fn create_export_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let mut commands = HashMap::new();
+    add_action_commands(
+        &mut commands,
+        state.config.key_bindings.keys(Action::Back),
+        create_loaded_statistics_window,
+    );
+    let key_bindings = &state.config.key_bindings;
+    add_action_commands(&mut commands, key_bindings.keys(Action::ExportJson), |s| {
+        export_statistics(s, ExportFormat::Json)
+    });
+    add_action_commands(&mut commands, key_bindings.keys(Action::ExportCsv), |s| {
+        export_statistics(s, ExportFormat::Csv)
+    });
+    add_action_commands(
+        &mut commands,
+        key_bindings.keys(Action::ExportMarkdown),
+        |s| export_statistics(s, ExportFormat::Markdown),
+    );
+    Some(Window {
+        ui: Box::new(export_window),
+        commands,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RunRange;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn the_range_counts_the_failed_runs_too() {
+        let run = |failed: bool| TraceRun {
+            failed,
+            ..TraceRun::default()
+        };
+        let mut runs: Vec<TraceRun> = (0..12).map(|_| run(false)).collect();
+        runs.extend((0..5).map(|_| run(true)));
+        let statistics = StatisticsState {
+            runs,
+            range: RunRange::Last10,
+            ..StatisticsState::default()
+        };
+        assert_eq!(selected_runs(&statistics), (7..17).collect::<Vec<_>>());
+        assert_eq!(range_runs(&statistics), (7..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn the_dashboard_summarizes_the_runs_of_the_table() {
+        let run = |failed: bool| TraceRun {
+            failed,
+            ..TraceRun::default()
+        };
+        let runs: Vec<TraceRun> = (0..15).map(|i| run(i % 3 == 0)).collect();
+        let statistics = StatisticsState {
+            runs,
+            range: RunRange::Last10,
+            ..StatisticsState::default()
+        };
+        let selected = selected_runs(&statistics);
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let summary = RunSummary::new(&statistics.runs, &selected, today);
+        assert_eq!(summary.run_count, range_runs(&statistics).len());
+        assert_eq!(summary.run_count + summary.failed_runs, 10);
+    }
+}
@@ -1,74 +1,377 @@
 use crate::add_to_commands;
+use crate::config::{key_to_string, Action, Config};
 use crate::generate_all_chars;
-use crate::get_app_path;
-use crate::windows::create_main_menu_window;
+use crate::library::{OptionsState, Selection};
+use crate::profile::{
+    create_profile, current_profile, delete_profile, invalid_char_message, is_username_char,
+    list_profiles, migrate_single_user, rename_profile, set_current_profile, too_long_message,
+    validate_username, MAX_USERNAME_LENGTH,
+};
+use crate::windows::{centered_rect, create_error_window, create_main_menu_window};
 use crate::State;
 use crate::Window;
+use crate::WindowAction;
 use crate::WindowCommand;
-use crossterm::event::KeyCode;
+use crate::WindowRender;
+use crate::{add_action_commands, LibraryState, ProfilesState, StatisticsState};
+use crossterm::event::{KeyCode, KeyEvent};
 use std::collections::HashMap;
 use std::rc::Rc;
 use tui::backend::Backend;
 use tui::layout::Alignment;
-use tui::style::Color;
-use tui::style::Style;
+use tui::style::Modifier;
 use tui::text::Span;
 use tui::text::Spans;
 use tui::widgets::Paragraph;
-use tui::Frame;
 
-fn user_window<B: 'static + Backend>(state: Rc<State>) -> Box<dyn Fn(&mut Frame<B>)> {
+fn user_window<B: 'static + Backend>(state: Rc<State>) -> WindowRender<B> {
     Box::new(move |f| {
-        let paragraph = Paragraph::new(vec![
-            Spans::from("Please write your username:"),
-            Spans::from(vec![Span::styled(
-                state.player.user_name.clone(),
-                Style::default().fg(Color::Yellow),
-            )]),
-        ])
-        .alignment(Alignment::Center);
-        f.render_widget(paragraph, f.size());
+        let theme = &state.config.theme;
+        let profiles = &state.profiles;
+        let key = |action: Action| {
+            state
+                .config
+                .key_bindings
+                .keys(action)
+                .first()
+                .map(|key| key_to_string(*key))
+                .unwrap_or_default()
+        };
+
+        let mut lines = Vec::new();
+        let help = match &profiles.input {
+            Some(input) => {
+                let prompt = if profiles.renaming {
+                    format!("New name for {}:", profiles.names[profiles.cursor])
+                } else {
+                    "Please write your username:".to_string()
+                };
+                lines.push(Spans::from(prompt));
+                lines.push(Spans::from(Span::styled(input.clone(), theme.highlight)));
+                if profiles.names.is_empty() {
+                    "[Enter] Save".to_string()
+                } else {
+                    format!("[Enter] Save  [{}] Cancel", key(Action::Back))
+                }
+            }
+            None => {
+                lines.push(Spans::from(Span::styled(
+                    "Profiles",
+                    theme.title.add_modifier(Modifier::BOLD),
+                )));
+                lines.push(Spans::from(""));
+                for (i, name) in profiles.names.iter().enumerate() {
+                    lines.push(if i == profiles.cursor {
+                        Spans::from(Span::styled(
+                            format!("► {}", name),
+                            theme.highlight.add_modifier(Modifier::BOLD),
+                        ))
+                    } else {
+                        Spans::from(Span::styled(format!("  {}", name), theme.text))
+                    });
+                }
+                format!(
+                    "[Up/Down] Move  [Enter] Play  [{}] New  [{}] Rename  [{}] Delete  [{}] Back",
+                    key(Action::NewProfile),
+                    key(Action::RenameProfile),
+                    key(Action::DeleteProfile),
+                    key(Action::Back),
+                )
+            }
+        };
+        lines.push(Spans::from(Span::styled(
+            profiles.message.clone().unwrap_or_default(),
+            theme.error,
+        )));
+        lines.push(Spans::from(Span::styled(help, theme.border)));
+
+        let height = lines.len() as u16;
+        let paragraph = Paragraph::new(lines).alignment(Alignment::Center);
+        f.render_widget(paragraph, centered_rect(f.size(), f.size().width, height));
     })
 }
 
-fn handle_char_press<B: 'static + Backend>(
-    c: char,
-) -> Box<dyn Fn(&mut State) -> Option<Window<B>>> {
+/// Reads the profiles again, keeping `selected` under the cursor.
+fn reload_profiles(profiles: &mut ProfilesState, selected: Option<&str>) {
+    profiles.names = list_profiles();
+    profiles.cursor = selected
+        .and_then(|selected| profiles.names.iter().position(|name| name == selected))
+        .unwrap_or(0);
+    profiles.input = if profiles.names.is_empty() {
+        Some(String::new())
+    } else {
+        None
+    };
+    profiles.renaming = false;
+    profiles.confirm_delete = false;
+}
+
+/// Plays with the profile, loading its settings.
+fn select_profile<B: 'static + Backend>(state: &mut State, name: String) -> Option<Window<B>> {
+    if let Err(err) = set_current_profile(&name) {
+        state.profiles.message = Some(format!("Couldn't select the profile: {}", err));
+        return create_loaded_user_window(state);
+    }
+    match Config::load_profile(&name) {
+        Ok(config) => state.config = config,
+        Err(err) => return create_error_window(err, create_user_window),
+    }
+    state.selection = Selection::load(&name);
+    state.player.user_name = name;
+    state.statistics = StatisticsState::default();
+    create_main_menu_window(state)
+}
+
+/// Forgets the settings and state of the deleted profile that was being played, going back to the
+/// ones of the app directory.
+fn clear_active_profile(state: &mut State) {
+    state.player.user_name.clear();
+    state.config = Config::load().unwrap_or_default();
+    state.selection = Selection::default();
+    state.statistics = StatisticsState::default();
+    state.library = LibraryState::default();
+    state.options = OptionsState::default();
+    state.book = None;
+    state.daily = None;
+}
+
+/// Profiles the name typed can't clash with. A profile can be renamed to its own name with
+/// other case.
+fn other_profiles(profiles: &ProfilesState) -> Vec<String> {
+    profiles
+        .names
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !profiles.renaming || *i != profiles.cursor)
+        .map(|(_, name)| name.clone())
+        .collect()
+}
+
+/// Chars that can't be used are rejected as they're typed, and the name is checked after every
+/// char so the problems show up before pressing enter.
+fn handle_char_press<B: 'static + Backend>(c: char) -> WindowAction<B> {
     Box::new(move |state: &mut State| {
-        state.player.user_name.push(c);
-        create_user_window(state)
+        let others = other_profiles(&state.profiles);
+        let profiles = &mut state.profiles;
+        if let Some(input) = &mut profiles.input {
+            profiles.message = if !is_username_char(c) {
+                Some(invalid_char_message(c))
+            } else if input.chars().count() >= MAX_USERNAME_LENGTH {
+                Some(too_long_message())
+            } else {
+                input.push(c);
+                validate_username(input, &others).err()
+            };
+        }
+        create_loaded_user_window(state)
     })
 }
 
-pub fn create_user_window<B: 'static + Backend>(_: &mut State) -> Option<Window<B>> {
-    let chars = generate_all_chars();
-    let mut commands = HashMap::new();
-    add_to_commands(&mut commands, &chars, Box::new(handle_char_press));
+fn handle_backspace_press<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let others = other_profiles(&state.profiles);
+    let profiles = &mut state.profiles;
+    if let Some(input) = &mut profiles.input {
+        input.pop();
+        profiles.message = if input.is_empty() {
+            None
+        } else {
+            validate_username(input, &others).err()
+        };
+    }
+    create_loaded_user_window(state)
+}
 
-    fn handle_backspace_press<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
-        state.player.user_name.pop();
-        create_user_window(state)
+fn handle_enter_press<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let profiles = &mut state.profiles;
+    let input = match profiles.input.clone() {
+        Some(input) => input,
+        None => {
+            let name = profiles.names[profiles.cursor].clone();
+            return select_profile(state, name);
+        }
+    };
+    let old_name = profiles
+        .names
+        .get(profiles.cursor)
+        .cloned()
+        .unwrap_or_default();
+    if profiles.renaming && input.trim() == old_name {
+        reload_profiles(profiles, Some(&old_name));
+        return create_loaded_user_window(state);
     }
+    let name = match validate_username(&input, &other_profiles(profiles)) {
+        Ok(name) => name,
+        Err(message) => {
+            profiles.message = Some(message);
+            return create_loaded_user_window(state);
+        }
+    };
+
+    if !profiles.renaming {
+        if let Err(err) = create_profile(&name) {
+            profiles.message = Some(format!("Couldn't create the profile: {}", err));
+            return create_loaded_user_window(state);
+        }
+        return select_profile(state, name);
+    }
+    match rename_profile(&old_name, &name) {
+        Ok(()) => {
+            if state.player.user_name == old_name {
+                state.player.user_name = name.clone();
+            }
+            reload_profiles(profiles, Some(&name));
+            profiles.message = None;
+        }
+        Err(err) => profiles.message = Some(format!("Couldn't rename the profile: {}", err)),
+    }
+    create_loaded_user_window(state)
+}
+
+/// Cancels the name being typed, or leaves the window. There's nowhere to go back to without a
+/// profile, so it closes the app.
+fn handle_back<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let profiles = &mut state.profiles;
+    if profiles.input.is_some() && !profiles.names.is_empty() {
+        let selected = profiles.names[profiles.cursor].clone();
+        reload_profiles(profiles, Some(&selected));
+        profiles.message = None;
+        return create_loaded_user_window(state);
+    }
+    if state.player.user_name.is_empty() {
+        None
+    } else {
+        create_main_menu_window(state)
+    }
+}
+
+/// Opens the profile window, selecting the profile used last.
+pub fn create_user_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let message = migrate_single_user()
+        .err()
+        .map(|err| format!("Couldn't move the old user to a profile: {}", err));
+    let selected = if state.player.user_name.is_empty() {
+        current_profile()
+    } else {
+        Some(state.player.user_name.clone())
+    };
+    reload_profiles(&mut state.profiles, selected.as_deref());
+    state.profiles.message = message;
+    create_loaded_user_window(state)
+}
+
+fn create_loaded_user_window<B: 'static + Backend>(state: &mut State) -> Option<Window<B>> {
+    let key_bindings = &state.config.key_bindings;
+    let mut commands = HashMap::new();
+    add_action_commands(&mut commands, key_bindings.keys(Action::Back), handle_back);
     commands.insert(
-        KeyCode::Backspace,
-        WindowCommand {
-            activator_key: KeyCode::Backspace,
-            action: Box::new(handle_backspace_press),
-        },
+        KeyEvent::from(KeyCode::Enter),
+        WindowCommand::new_key_command(KeyCode::Enter, Box::new(handle_enter_press)),
     );
-    commands.insert(
-        KeyCode::Enter,
-        WindowCommand {
-            activator_key: KeyCode::Enter,
-            action: Box::new(|state| {
-                let path = get_app_path(".user");
-                match std::fs::write(path, state.player.user_name.to_string()) {
-                    Ok(_) => create_main_menu_window(state),
-                    Err(_) => create_user_window(state),
+
+    if state.profiles.input.is_some() {
+        // Typing a name uses every char, even the ones bound to actions.
+        let chars = generate_all_chars();
+        add_to_commands(&mut commands, &chars, Box::new(handle_char_press));
+        commands.insert(
+            KeyEvent::from(KeyCode::Backspace),
+            WindowCommand::new_key_command(KeyCode::Backspace, Box::new(handle_backspace_press)),
+        );
+    } else {
+        commands.insert(
+            KeyEvent::from(KeyCode::Up),
+            WindowCommand::new_key_command(
+                KeyCode::Up,
+                Box::new(|state: &mut State| {
+                    let profiles = &mut state.profiles;
+                    profiles.cursor = profiles.cursor.saturating_sub(1);
+                    profiles.confirm_delete = false;
+                    profiles.message = None;
+                    create_loaded_user_window(state)
+                }),
+            ),
+        );
+        commands.insert(
+            KeyEvent::from(KeyCode::Down),
+            WindowCommand::new_key_command(
+                KeyCode::Down,
+                Box::new(|state: &mut State| {
+                    let profiles = &mut state.profiles;
+                    profiles.cursor = std::cmp::min(profiles.cursor + 1, profiles.names.len() - 1);
+                    profiles.confirm_delete = false;
+                    profiles.message = None;
+                    create_loaded_user_window(state)
+                }),
+            ),
+        );
+        add_action_commands(
+            &mut commands,
+            key_bindings.keys(Action::NewProfile),
+            |state: &mut State| {
+                let profiles = &mut state.profiles;
+                profiles.input = Some(String::new());
+                profiles.renaming = false;
+                profiles.confirm_delete = false;
+                profiles.message = None;
+                create_loaded_user_window(state)
+            },
+        );
+        add_action_commands(
+            &mut commands,
+            key_bindings.keys(Action::RenameProfile),
+            |state: &mut State| {
+                let profiles = &mut state.profiles;
+                profiles.input = Some(profiles.names[profiles.cursor].clone());
+                profiles.renaming = true;
+                profiles.confirm_delete = false;
+                profiles.message = None;
+                create_loaded_user_window(state)
+            },
+        );
+        add_action_commands(
+            &mut commands,
+            key_bindings.keys(Action::DeleteProfile),
+            |state: &mut State| {
+                let profiles = &mut state.profiles;
+                let name = profiles.names[profiles.cursor].clone();
+                if !profiles.confirm_delete {
+                    let key = state
+                        .config
+                        .key_bindings
+                        .keys(Action::DeleteProfile)
+                        .first()
+                        .map(|key| key_to_string(*key))
+                        .unwrap_or_default();
+                    profiles.confirm_delete = true;
+                    profiles.message = Some(format!(
+                        "Press [{}] again to delete {} and all its runs",
+                        key, name
+                    ));
+                    return create_loaded_user_window(state);
                 }
-            }),
-        },
-    );
+                match delete_profile(&name) {
+                    Ok(()) => {
+                        if state.player.user_name == name {
+                            clear_active_profile(state);
+                        }
+                        let profiles = &mut state.profiles;
+                        // The cursor stays in the same place of the list.
+                        let next = profiles
+                            .names
+                            .get(profiles.cursor + 1)
+                            .or_else(|| profiles.names.get(profiles.cursor.checked_sub(1)?))
+                            .cloned();
+                        reload_profiles(profiles, next.as_deref());
+                        profiles.message = None;
+                    }
+                    Err(err) => {
+                        profiles.confirm_delete = false;
+                        profiles.message = Some(format!("Couldn't delete the profile: {}", err));
+                    }
+                }
+                create_loaded_user_window(state)
+            },
+        );
+    }
 
     Some(Window {
         ui: Box::new(user_window),